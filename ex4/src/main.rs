@@ -1,158 +1,631 @@
-use std::fmt::{self, Display, Formatter};
+mod map;
+mod persistent;
+mod rbtree;
+mod set;
 
+use std::cmp::Ordering;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::ops::{Bound, RangeBounds};
+use std::ptr;
+
+pub use map::TreeMap;
+pub use persistent::PersistentTree;
+pub use rbtree::RbTree;
+pub use set::{Difference, Intersection, SymmetricDifference, Union};
+
+#[derive(Clone)]
 struct Node<T> {
     left: Option<Box<Node<T>>>,
     element: T,
     right: Option<Box<Node<T>>>,
+    height: usize,
+    size: usize,
+}
+
+impl<T> Node<T> {
+    fn new(element: T) -> Box<Self> {
+        Box::new(Node { left: None, element, right: None, height: 1, size: 1 })
+    }
+
+    fn update(&mut self) {
+        self.height = 1 + height(&self.left).max(height(&self.right));
+        self.size = 1 + size(&self.left) + size(&self.right);
+    }
+
+    fn balance_factor(&self) -> isize {
+        height(&self.left) as isize - height(&self.right) as isize
+    }
+}
+
+fn height<T>(node: &Option<Box<Node<T>>>) -> usize {
+    node.as_ref().map_or(0, |x| x.height)
+}
+
+fn size<T>(node: &Option<Box<Node<T>>>) -> usize {
+    node.as_ref().map_or(0, |x| x.size)
+}
+
+fn rotate_right<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    let mut left = node.left.take().unwrap();
+    node.left = left.right.take();
+    node.update();
+    left.right = Some(node);
+    left.update();
+    left
+}
+
+fn rotate_left<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    let mut right = node.right.take().unwrap();
+    node.right = right.left.take();
+    node.update();
+    right.left = Some(node);
+    right.update();
+    right
+}
+
+fn rebalance<T>(node: &mut Option<Box<Node<T>>>) {
+    let Some(mut x) = node.take() else {
+        return;
+    };
+    x.update();
+    let balance = x.balance_factor();
+    if balance > 1 {
+        if x.left.as_ref().unwrap().balance_factor() < 0 {
+            x.left = Some(rotate_left(x.left.take().unwrap()));
+        }
+        x = rotate_right(x);
+    } else if balance < -1 {
+        if x.right.as_ref().unwrap().balance_factor() > 0 {
+            x.right = Some(rotate_right(x.right.take().unwrap()));
+        }
+        x = rotate_left(x);
+    }
+    *node = Some(x);
+}
+
+fn reattach<T>(mut stack: Vec<(Box<Node<T>>, bool)>, mut subtree: Option<Box<Node<T>>>) -> Option<Box<Node<T>>> {
+    while let Some((mut node, right)) = stack.pop() {
+        if right {
+            node.right = subtree;
+        } else {
+            node.left = subtree;
+        }
+        subtree = Some(node);
+        rebalance(&mut subtree);
+    }
+    subtree
+}
+
+fn pop_min_node<T>(mut node: Box<Node<T>>) -> (Option<Box<Node<T>>>, Box<Node<T>>) {
+    let mut stack = Vec::new();
+    while let Some(left) = node.left.take() {
+        stack.push((node, false));
+        node = left;
+    }
+    let rest = node.right.take();
+    (reattach(stack, rest), node)
 }
 
+#[derive(Clone)]
 pub struct Tree<T> {
     root: Option<Box<Node<T>>>,
 }
 
+impl<T: Ord> Default for Tree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T: Ord> Tree<T> {
     pub fn new() -> Self {
         Tree { root: None }
     }
 
+    fn from_sorted(elements: Vec<T>) -> Self {
+        fn build<T>(elements: &mut impl Iterator<Item = T>, len: usize) -> Option<Box<Node<T>>> {
+            if len == 0 {
+                return None;
+            }
+            let left = build(elements, len / 2);
+            let mut node = Node::new(elements.next().unwrap());
+            node.left = left;
+            node.right = build(elements, len - len / 2 - 1);
+            node.update();
+            Some(node)
+        }
+        let len = elements.len();
+        Tree { root: build(&mut elements.into_iter(), len) }
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn select(&self, mut k: usize) -> Option<&T> {
+        let mut current = &self.root;
+        while let Some(node) = current {
+            let left = size(&node.left);
+            if k < left {
+                current = &node.left;
+            } else if k == left {
+                return Some(&node.element);
+            } else {
+                k -= left + 1;
+                current = &node.right;
+            }
+        }
+        None
+    }
+
+    fn select_mut(&mut self, mut k: usize) -> Option<&mut T> {
+        let mut current = &mut self.root;
+        while let Some(node) = current {
+            let left = size(&node.left);
+            if k < left {
+                current = &mut node.left;
+            } else if k == left {
+                return Some(&mut node.element);
+            } else {
+                k -= left + 1;
+                current = &mut node.right;
+            }
+        }
+        None
+    }
+
+    pub fn rank(&self, key: &T) -> usize {
+        let mut rank = 0;
+        let mut current = &self.root;
+        while let Some(node) = current {
+            if &node.element < key {
+                rank += size(&node.left) + 1;
+                current = &node.right;
+            } else {
+                current = &node.left;
+            }
+        }
+        rank
+    }
+
     pub fn insert(&mut self, element: T) {
-        fn insert_recursive<T: Ord>(node: &mut Option<Box<Node<T>>>, element: T) {
-            match node {
-                None => {
-                    *node = Some(Box::new(Node { element, left: None, right: None }));
-                }
-                Some(x) => {
-                    if element <= x.element {
-                        insert_recursive(&mut x.left, element);
-                    } else {
-                        insert_recursive(&mut x.right, element);
-                    }
-                }
+        self.insert_entry(element);
+    }
+
+    fn insert_entry(&mut self, element: T) -> &mut T {
+        let mut stack = Vec::new();
+        let mut index = 0;
+        let mut current = self.root.take();
+        while let Some(mut node) = current {
+            let right = element > node.element;
+            if right {
+                index += size(&node.left) + 1;
             }
+            current = if right { node.right.take() } else { node.left.take() };
+            stack.push((node, right));
         }
-        insert_recursive(&mut self.root, element);
+        self.root = reattach(stack, Some(Node::new(element)));
+        self.select_mut(index).unwrap()
+    }
+
+    fn find_by(&self, cmp: impl Fn(&T) -> Ordering) -> Option<&T> {
+        let mut current = &self.root;
+        while let Some(x) = current {
+            match cmp(&x.element) {
+                Ordering::Less => current = &x.left,
+                Ordering::Greater => current = &x.right,
+                Ordering::Equal => return Some(&x.element),
+            }
+        }
+        None
+    }
+
+    fn find_by_mut(&mut self, cmp: impl Fn(&T) -> Ordering) -> Option<&mut T> {
+        let mut current = &mut self.root;
+        while let Some(x) = current {
+            match cmp(&x.element) {
+                Ordering::Less => current = &mut x.left,
+                Ordering::Greater => current = &mut x.right,
+                Ordering::Equal => return Some(&mut x.element),
+            }
+        }
+        None
     }
 
     pub fn pop_max(&mut self) -> Option<T> {
-        fn pop_max_recursive<T: Ord>(node: &mut Option<Box<Node<T>>>) -> Option<T> {
-            if let Some(x) = node {
-                if x.right.is_some() {
-                    return pop_max_recursive(&mut x.right);
+        let mut stack = Vec::new();
+        let mut node = self.root.take()?;
+        while let Some(right) = node.right.take() {
+            stack.push((node, true));
+            node = right;
+        }
+        self.root = reattach(stack, node.left.take());
+        Some(node.element)
+    }
+
+    pub fn remove(&mut self, key: &T) -> bool {
+        self.remove_by(|x| key.cmp(x)).is_some()
+    }
+
+    fn remove_by(&mut self, cmp: impl Fn(&T) -> Ordering) -> Option<T> {
+        let mut stack = Vec::new();
+        let mut current = self.root.take();
+        let mut removed = loop {
+            let Some(mut node) = current else {
+                self.root = reattach(stack, None);
+                return None;
+            };
+            match cmp(&node.element) {
+                Ordering::Less => {
+                    current = node.left.take();
+                    stack.push((node, false));
                 }
-                let mut boxed = node.take().unwrap();
-                *node = boxed.left.take();
-                return Some(boxed.element);
-            }
-            None
-        }
-        pop_max_recursive(&mut self.root)
-    }
-
-    pub fn remove(&mut self, key: &T) -> bool
-    where
-        T: Clone,
-    {
-        fn remove_recursive<T: Ord + Clone>(node: &mut Option<Box<Node<T>>>, key: &T) -> bool {
-            if let Some(x) = node {
-                if key < &x.element {
-                    return remove_recursive(&mut x.left, key);
-                } else if key > &x.element {
-                    return remove_recursive(&mut x.right, key);
-                } else {
-                    match (x.left.take(), x.right.take()) {
-                        (None, None) => *node = None,
-                        (Some(left), None) => *node = Some(left),
-                        (None, Some(right)) => *node = Some(right),
-                        (Some(left), Some(right_sub)) => {
-                            let mut min_val = right_sub.element.clone();
-                            {
-                                let mut cur = &right_sub;
-                                while let Some(ref l) = cur.left {
-                                    min_val = l.element.clone();
-                                    cur = l;
-                                }
-                            }
-                            let mut right_opt = Some(right_sub);
-                            remove_recursive(&mut right_opt, &min_val);
-
-                            *node = Some(Box::new(Node {
-                                element: min_val,
-                                left: Some(left),
-                                right: right_opt,
-                            }));
-                        }
-                    }
-                    return true;
+                Ordering::Greater => {
+                    current = node.right.take();
+                    stack.push((node, true));
                 }
+                Ordering::Equal => break node,
             }
-            false
-        }
-        remove_recursive(&mut self.root, key)
+        };
+
+        let replacement = match (removed.left.take(), removed.right.take()) {
+            (left, None) => left,
+            (None, right) => right,
+            (Some(left), Some(right)) => {
+                let (right, mut successor) = pop_min_node(right);
+                successor.left = Some(left);
+                successor.right = right;
+                let mut successor = Some(successor);
+                rebalance(&mut successor);
+                successor
+            }
+        };
+        self.root = reattach(stack, replacement);
+        Some(removed.element)
     }
 
     pub fn inorder(&self) -> Vec<&T> {
-        fn inorder_recursive<'a, T>(node: &'a Option<Box<Node<T>>>, acc: &mut Vec<&'a T>) {
-            if let Some(x) = node {
-                inorder_recursive(&x.left, acc);
-                acc.push(&x.element);
-                inorder_recursive(&x.right, acc);
+        self.iter().collect()
+    }
+
+    pub fn iter(&self) -> TreeIter<'_, T> {
+        let mut front = Vec::new();
+        let mut back = Vec::new();
+        push_left(&mut front, &self.root);
+        push_right(&mut back, &self.root);
+        TreeIter { front, back, len: self.len() }
+    }
+
+    fn iter_mut(&mut self) -> TreeIterMut<'_, T> {
+        let len = self.len();
+        let mut iter = TreeIterMut { stack: Vec::new(), len };
+        iter.push_left(&mut self.root);
+        iter
+    }
+
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> Range<'_, T> {
+        let mut stack = Vec::new();
+        let mut current = &self.root;
+        while let Some(node) = current {
+            if above_start(range.start_bound(), &node.element) {
+                stack.push(&**node);
+                current = &node.left;
+            } else {
+                current = &node.right;
             }
         }
-        let mut vec = Vec::new();
-        inorder_recursive(&self.root, &mut vec);
-        vec
+        let last = self.last_node_where(|x| below_end(range.end_bound(), x));
+        if stack.last().is_some_and(|first| !below_end(range.end_bound(), &first.element)) {
+            stack.clear();
+        }
+        Range { stack, last }
     }
 
-    pub fn iter(&self) -> TreeIter<T> {
-        let mut stack = Vec::new();
+    pub fn floor(&self, key: &T) -> Option<&T> {
+        self.last_node_where(|x| x <= key).map(|node| &node.element)
+    }
+
+    pub fn ceiling(&self, key: &T) -> Option<&T> {
+        self.range((Bound::Included(key), Bound::Unbounded)).next()
+    }
+
+    pub fn predecessor(&self, key: &T) -> Option<&T> {
+        self.last_node_where(|x| x < key).map(|node| &node.element)
+    }
+
+    pub fn successor(&self, key: &T) -> Option<&T> {
+        self.range((Bound::Excluded(key), Bound::Unbounded)).next()
+    }
+
+    fn last_node_where(&self, pred: impl Fn(&T) -> bool) -> Option<&Node<T>> {
+        let mut found = None;
         let mut current = &self.root;
         while let Some(node) = current {
-            stack.push(&**node);
-            current = &node.left;
+            if pred(&node.element) {
+                found = Some(&**node);
+                current = &node.right;
+            } else {
+                current = &node.left;
+            }
+        }
+        found
+    }
+}
+
+fn above_start<T: Ord>(start: Bound<&T>, x: &T) -> bool {
+    match start {
+        Bound::Included(start) => x >= start,
+        Bound::Excluded(start) => x > start,
+        Bound::Unbounded => true,
+    }
+}
+
+fn below_end<T: Ord>(end: Bound<&T>, x: &T) -> bool {
+    match end {
+        Bound::Included(end) => x <= end,
+        Bound::Excluded(end) => x < end,
+        Bound::Unbounded => true,
+    }
+}
+
+impl<T: Ord> FromIterator<T> for Tree<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut tree = Tree::new();
+        tree.extend(iter);
+        tree
+    }
+}
+
+impl<T: Ord> Extend<T> for Tree<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for element in iter {
+            self.insert(element);
+        }
+    }
+}
+
+impl<'a, T: Ord + Copy + 'a> Extend<&'a T> for Tree<T> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T: Debug + Ord> Debug for Tree<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Ord> PartialEq for Tree<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Ord> Eq for Tree<T> {}
+
+impl<T: Ord> PartialOrd for Tree<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord> Ord for Tree<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl<T: Ord + Hash> Hash for Tree<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        for element in self {
+            element.hash(state);
         }
-        TreeIter { stack }
     }
 }
 
 impl<T: Display + Ord> Display for Tree<T> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        fn fmt_node<T: Display>(node: &Option<Box<Node<T>>>, f: &mut Formatter<'_>, depth: usize) -> fmt::Result {
-            if let Some(x) = node {
-                fmt_node(&x.right, f, depth + 1)?;
-                for _ in 0..depth {
-                    write!(f, "    ")?;
-                }
-                writeln!(f, "{}", x.element)?;
-                fmt_node(&x.left, f, depth + 1)?;
+        let mut stack = Vec::new();
+        let (mut current, mut depth) = (&self.root, 0);
+        loop {
+            while let Some(node) = current {
+                stack.push((&**node, depth));
+                current = &node.right;
+                depth += 1;
             }
-            Ok(())
+            let Some((node, node_depth)) = stack.pop() else {
+                return Ok(());
+            };
+            for _ in 0..node_depth {
+                write!(f, "    ")?;
+            }
+            writeln!(f, "{}", node.element)?;
+            current = &node.left;
+            depth = node_depth + 1;
         }
-        fmt_node(&self.root, f, 0)
+    }
+}
+
+impl<T> Drop for Tree<T> {
+    fn drop(&mut self) {
+        let mut stack: Vec<_> = self.root.take().into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+    }
+}
+
+fn push_left<'a, T>(stack: &mut Vec<&'a Node<T>>, mut current: &'a Option<Box<Node<T>>>) {
+    while let Some(node) = current {
+        stack.push(&**node);
+        current = &node.left;
+    }
+}
+
+fn push_right<'a, T>(stack: &mut Vec<&'a Node<T>>, mut current: &'a Option<Box<Node<T>>>) {
+    while let Some(node) = current {
+        stack.push(&**node);
+        current = &node.right;
+    }
+}
+
+impl<'a, T: Ord> IntoIterator for &'a Tree<T> {
+    type Item = &'a T;
+    type IntoIter = TreeIter<'a, T>;
+
+    fn into_iter(self) -> TreeIter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T: Ord> IntoIterator for Tree<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(mut self) -> IntoIter<T> {
+        let len = self.len();
+        let mut iter = IntoIter { stack: Vec::new(), len };
+        iter.push_left(self.root.take());
+        iter
     }
 }
 
 pub struct TreeIter<'a, T> {
-    stack: Vec<&'a Node<T>>,
+    front: Vec<&'a Node<T>>,
+    back: Vec<&'a Node<T>>,
+    len: usize,
 }
 
 impl<'a, T> Iterator for TreeIter<'a, T> {
     type Item = &'a T;
 
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        let node = self.front.pop()?;
+        push_left(&mut self.front, &node.right);
+        Some(&node.element)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for TreeIter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        let node = self.back.pop()?;
+        push_right(&mut self.back, &node.left);
+        Some(&node.element)
+    }
+}
+
+impl<T> ExactSizeIterator for TreeIter<'_, T> {}
+
+struct TreeIterMut<'a, T> {
+    stack: Vec<(&'a mut T, &'a mut Option<Box<Node<T>>>)>,
+    len: usize,
+}
+
+impl<'a, T> TreeIterMut<'a, T> {
+    fn push_left(&mut self, mut current: &'a mut Option<Box<Node<T>>>) {
+        while let Some(node) = current {
+            let Node { left, element, right, .. } = &mut **node;
+            self.stack.push((element, right));
+            current = left;
+        }
+    }
+}
+
+impl<'a, T> Iterator for TreeIterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (element, right) = self.stack.pop()?;
+        self.len -= 1;
+        self.push_left(right);
+        Some(element)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+pub struct IntoIter<T> {
+    stack: Vec<Box<Node<T>>>,
+    len: usize,
+}
+
+impl<T> IntoIter<T> {
+    fn push_left(&mut self, mut current: Option<Box<Node<T>>>) {
+        while let Some(mut node) = current {
+            current = node.left.take();
+            self.stack.push(node);
+        }
+    }
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut node = self.stack.pop()?;
+        self.len -= 1;
+        self.push_left(node.right.take());
+        Some(node.element)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> Drop for IntoIter<T> {
+    fn drop(&mut self) {
+        for _ in self.by_ref() {}
+    }
+}
+
+pub struct Range<'a, T> {
+    stack: Vec<&'a Node<T>>,
+    last: Option<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Range<'a, T> {
+    type Item = &'a T;
+
     fn next(&mut self) -> Option<Self::Item> {
         let node = self.stack.pop()?;
 
-        let mut current = &node.right;
-        while let Some(inner) = current {
-            self.stack.push(&**inner);
-            current = &inner.left;
+        if self.last.is_some_and(|last| ptr::eq(node, last)) {
+            self.stack.clear();
+        } else {
+            push_left(&mut self.stack, &node.right);
         }
 
         Some(&node.element)
     }
 }
 
-
 #[cfg(test)]
+#[allow(clippy::items_after_test_module, clippy::map_clone)]
 mod tests {
     use super::*;
 
@@ -190,6 +663,297 @@ mod tests {
         let result = tree.inorder().iter().map(|x| **x).collect::<Vec<_>>();
         assert_eq!(result, vec![1, 3, 6, 8, 14]);
     }
+
+    fn assert_balanced<T: Ord>(node: &Option<Box<Node<T>>>) -> usize {
+        let Some(x) = node else {
+            return 0;
+        };
+        let left = assert_balanced(&x.left);
+        let right = assert_balanced(&x.right);
+        assert!(left.abs_diff(right) <= 1);
+        assert_eq!(x.height, 1 + left.max(right));
+        assert_eq!(x.size, 1 + size(&x.left) + size(&x.right));
+        x.height
+    }
+
+    #[test]
+    fn test_sorted_inserts_stay_balanced() {
+        let mut tree = Tree::new();
+        for i in 0..1000 {
+            tree.insert(i);
+        }
+        assert_eq!(assert_balanced(&tree.root), 10);
+        for i in (0..1000).step_by(3) {
+            assert!(tree.remove(&i));
+        }
+        assert!(!tree.remove(&0));
+        assert_balanced(&tree.root);
+        for _ in 0..100 {
+            tree.pop_max();
+        }
+        assert_balanced(&tree.root);
+        let expected = (0..1000).filter(|i| i % 3 != 0).take(566).collect::<Vec<_>>();
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_range() {
+        let mut tree = Tree::new();
+        for i in (0..100).step_by(2).rev() {
+            tree.insert(i);
+        }
+        tree.insert(10);
+        let collect = |range: Range<i32>| range.copied().collect::<Vec<_>>();
+        assert_eq!(collect(tree.range(10..20)), vec![10, 10, 12, 14, 16, 18]);
+        assert_eq!(collect(tree.range(11..=20)), vec![12, 14, 16, 18, 20]);
+        assert_eq!(collect(tree.range(..3)), vec![0, 2]);
+        assert_eq!(collect(tree.range(95..)), vec![96, 98]);
+        assert_eq!(collect(tree.range((Bound::Excluded(10), Bound::Excluded(14)))), vec![12]);
+        assert!(tree.range(11..12).next().is_none());
+        assert!(tree.range((Bound::Included(20), Bound::Excluded(10))).next().is_none());
+        assert!(tree.range(200..).next().is_none());
+        assert_eq!(tree.range(..).count(), 51);
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let mut tree = Tree::new();
+        for i in [10, 20, 30] {
+            tree.insert(i);
+        }
+        assert_eq!(tree.floor(&20), Some(&20));
+        assert_eq!(tree.floor(&25), Some(&20));
+        assert_eq!(tree.floor(&5), None);
+        assert_eq!(tree.ceiling(&20), Some(&20));
+        assert_eq!(tree.ceiling(&25), Some(&30));
+        assert_eq!(tree.ceiling(&35), None);
+        assert_eq!(tree.predecessor(&20), Some(&10));
+        assert_eq!(tree.predecessor(&10), None);
+        assert_eq!(tree.successor(&20), Some(&30));
+        assert_eq!(tree.successor(&30), None);
+    }
+
+    #[test]
+    fn test_order_statistics() {
+        let mut tree = Tree::new();
+        assert_eq!((tree.len(), tree.select(0), tree.rank(&5)), (0, None, 0));
+        for i in (0..100).rev() {
+            tree.insert(i * 10);
+        }
+        tree.insert(500);
+        assert_eq!(tree.len(), 101);
+        assert_eq!(tree.select(0), Some(&0));
+        assert_eq!(tree.select(50), Some(&500));
+        assert_eq!(tree.select(51), Some(&500));
+        assert_eq!(tree.select(100), Some(&990));
+        assert_eq!(tree.select(101), None);
+        assert_eq!(tree.rank(&500), 50);
+        assert_eq!(tree.rank(&505), 52);
+        assert_eq!(tree.rank(&-1), 0);
+        assert_eq!(tree.rank(&1000), 101);
+
+        tree.remove(&0);
+        tree.pop_max();
+        assert_balanced(&tree.root);
+        assert_eq!(tree.len(), 99);
+        assert_eq!(tree.select(0), Some(&10));
+        assert_eq!(tree.rank(&500), 49);
+        let p90 = tree.select(tree.len() * 9 / 10).unwrap();
+        assert_eq!(*p90, 890);
+    }
+
+    #[test]
+    fn test_double_ended_and_owning_iterators() {
+        let mut tree = Tree::new();
+        for i in [4, 2, 6, 1, 3, 5, 7] {
+            tree.insert(i);
+        }
+        assert_eq!(tree.iter().rev().copied().collect::<Vec<_>>(), vec![7, 6, 5, 4, 3, 2, 1]);
+
+        let mut iter = tree.iter();
+        assert_eq!(iter.len(), 7);
+        assert_eq!((iter.next(), iter.next_back()), (Some(&1), Some(&7)));
+        assert_eq!((iter.next_back(), iter.next()), (Some(&6), Some(&2)));
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.copied().collect::<Vec<_>>(), vec![3, 4, 5]);
+
+        let mut sum = 0;
+        for x in &tree {
+            sum += x;
+        }
+        assert_eq!(sum, 28);
+
+        let mut owned = tree.into_iter();
+        assert_eq!(owned.len(), 7);
+        assert_eq!(owned.next(), Some(1));
+        assert_eq!(owned.collect::<Vec<_>>(), vec![2, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn test_into_iter_moves_elements() {
+        let mut tree = Tree::new();
+        for word in ["pera", "uva", "maçã"] {
+            tree.insert(word.to_string());
+        }
+        let words: Vec<String> = tree.into_iter().collect();
+        assert_eq!(words, vec!["maçã", "pera", "uva"]);
+    }
+
+    #[test]
+    fn test_collection_traits() {
+        let tree: Tree<i32> = [3, 1, 2].into_iter().collect();
+        assert_eq!(format!("{:?}", tree), "{1, 2, 3}");
+        assert_eq!(format!("{:?}", Tree::<i32>::default()), "{}");
+
+        let mut copy = tree.clone();
+        assert_eq!(copy, tree);
+        copy.extend(&[0, 4]);
+        assert_ne!(copy, tree);
+        assert!(copy.remove(&0) && copy.remove(&4));
+        assert_eq!(copy, tree);
+
+        let shorter: Tree<i32> = [1, 2].into_iter().collect();
+        let bigger: Tree<i32> = [1, 3].into_iter().collect();
+        assert!(shorter < tree);
+        assert!(tree < bigger);
+        assert_eq!(tree.cmp(&copy), Ordering::Equal);
+
+        let hash = |tree: &Tree<i32>| {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            tree.hash(&mut hasher);
+            hasher.finish()
+        };
+        let other_shape: Tree<i32> = [1, 2, 3].into_iter().collect();
+        assert_eq!(hash(&tree), hash(&other_shape));
+
+        let mut seen = std::collections::HashSet::new();
+        assert!(seen.insert(tree));
+        assert!(!seen.insert(other_shape));
+    }
+
+    #[test]
+    fn test_matches_sorted_vec() {
+        let mut seed = 42u64;
+        let mut next = move || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) as i32 % 50
+        };
+        let mut tree = Tree::new();
+        let mut model = Vec::new();
+        for _ in 0..5000 {
+            let key = next();
+            match next() % 4 {
+                0 | 1 => {
+                    tree.insert(key);
+                    model.insert(model.partition_point(|x| *x <= key), key);
+                }
+                2 => {
+                    let position = model.iter().position(|x| *x == key);
+                    assert_eq!(tree.remove(&key), position.is_some());
+                    if let Some(position) = position {
+                        model.remove(position);
+                    }
+                }
+                _ => assert_eq!(tree.pop_max(), model.pop()),
+            }
+            assert!(tree.iter().eq(model.iter()));
+        }
+        assert_balanced(&tree.root);
+    }
+
+    #[test]
+    fn test_deep_trees_are_stack_safe() {
+        let mut tree = Tree::new();
+        for i in 0..100_000 {
+            tree.insert(i);
+        }
+        assert_eq!(tree.len(), 100_000);
+        assert_eq!(tree.to_string().lines().count(), 100_000);
+        assert_eq!(tree.inorder().len(), 100_000);
+        assert_eq!(tree.pop_max(), Some(99_999));
+        assert!(tree.remove(&0));
+
+        let mut skewed = Tree::new();
+        let mut node = None;
+        for i in (0..100_000).rev() {
+            node = Some(Box::new(Node { left: None, element: i, right: node, height: 1, size: 1 }));
+        }
+        skewed.root = node;
+        drop(skewed);
+    }
+
+    #[test]
+    fn test_from_sorted_is_balanced() {
+        for len in [0, 1, 2, 7, 8, 1000] {
+            let tree = Tree::from_sorted((0..len).collect());
+            assert_balanced(&tree.root);
+            assert!(tree.iter().copied().eq(0..len));
+        }
+    }
+
+    macro_rules! shared_suite {
+        ($name:ident, $tree:ident) => {
+            mod $name {
+                use super::super::$tree;
+
+                #[test]
+                fn test_inorder_and_iter_agree() {
+                    let mut tree = $tree::new();
+                    for i in [50, 20, 80, 10, 30, 70, 90, 20] {
+                        tree.insert(i);
+                    }
+                    assert_eq!(tree.inorder(), tree.iter().collect::<Vec<_>>());
+                    assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![10, 20, 20, 30, 50, 70, 80, 90]);
+                }
+
+                #[test]
+                fn test_remove() {
+                    let mut tree = $tree::new();
+                    for i in 0..100 {
+                        tree.insert(i);
+                    }
+                    assert!(!tree.remove(&100));
+                    for i in (0..100).filter(|i| i % 2 == 0) {
+                        assert!(tree.remove(&i));
+                    }
+                    assert!(!tree.remove(&0));
+                    assert_eq!(tree.iter().copied().collect::<Vec<_>>(), (1..100).step_by(2).collect::<Vec<_>>());
+                }
+
+                #[test]
+                fn test_pop_max_drains_in_order() {
+                    let mut tree = $tree::new();
+                    for i in [3, 1, 4, 1, 5, 9, 2, 6] {
+                        tree.insert(i);
+                    }
+                    let drained = std::iter::from_fn(|| tree.pop_max()).collect::<Vec<_>>();
+                    assert_eq!(drained, vec![9, 6, 5, 4, 3, 2, 1, 1]);
+                    assert!(tree.iter().next().is_none());
+                }
+
+                #[test]
+                fn test_sorted_inserts() {
+                    let mut tree = $tree::new();
+                    for i in 0..10_000 {
+                        tree.insert(i);
+                    }
+                    assert!(tree.iter().copied().eq(0..10_000));
+                }
+
+                #[test]
+                fn test_display() {
+                    let mut tree = $tree::new();
+                    for i in [2, 1, 3] {
+                        tree.insert(i);
+                    }
+                    assert_eq!(tree.to_string(), "    3\n2\n    1\n");
+                }
+            }
+        };
+    }
+
+    shared_suite!(avl, Tree);
+    shared_suite!(red_black, RbTree);
 }
 
 fn main()
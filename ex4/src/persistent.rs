@@ -0,0 +1,234 @@
+use std::cmp::Ordering;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::Arc;
+
+struct Node<T> {
+    left: Option<Arc<Node<T>>>,
+    element: T,
+    right: Option<Arc<Node<T>>>,
+    height: usize,
+    size: usize,
+}
+
+fn height<T>(node: &Option<Arc<Node<T>>>) -> usize {
+    node.as_ref().map_or(0, |x| x.height)
+}
+
+fn size<T>(node: &Option<Arc<Node<T>>>) -> usize {
+    node.as_ref().map_or(0, |x| x.size)
+}
+
+fn make<T>(left: Option<Arc<Node<T>>>, element: T, right: Option<Arc<Node<T>>>) -> Arc<Node<T>> {
+    let height = 1 + height(&left).max(height(&right));
+    let size = 1 + size(&left) + size(&right);
+    Arc::new(Node { left, element, right, height, size })
+}
+
+fn balance<T: Clone>(left: Option<Arc<Node<T>>>, element: T, right: Option<Arc<Node<T>>>) -> Arc<Node<T>> {
+    if height(&left) > height(&right) + 1 {
+        let l = left.unwrap();
+        if height(&l.left) >= height(&l.right) {
+            return make(l.left.clone(), l.element.clone(), Some(make(l.right.clone(), element, right)));
+        }
+        let lr = l.right.as_ref().unwrap();
+        let new_left = make(l.left.clone(), l.element.clone(), lr.left.clone());
+        return make(Some(new_left), lr.element.clone(), Some(make(lr.right.clone(), element, right)));
+    }
+    if height(&right) > height(&left) + 1 {
+        let r = right.unwrap();
+        if height(&r.right) >= height(&r.left) {
+            return make(Some(make(left, element, r.left.clone())), r.element.clone(), r.right.clone());
+        }
+        let rl = r.left.as_ref().unwrap();
+        let new_right = make(rl.right.clone(), r.element.clone(), r.right.clone());
+        return make(Some(make(left, element, rl.left.clone())), rl.element.clone(), Some(new_right));
+    }
+    make(left, element, right)
+}
+
+fn remove_min<T: Clone>(node: &Arc<Node<T>>) -> (Option<Arc<Node<T>>>, T) {
+    match &node.left {
+        None => (node.right.clone(), node.element.clone()),
+        Some(left) => {
+            let (rest, min) = remove_min(left);
+            (Some(balance(rest, node.element.clone(), node.right.clone())), min)
+        }
+    }
+}
+
+pub struct PersistentTree<T> {
+    root: Option<Arc<Node<T>>>,
+}
+
+impl<T> Clone for PersistentTree<T> {
+    fn clone(&self) -> Self {
+        PersistentTree { root: self.root.clone() }
+    }
+}
+
+impl<T: Ord + Clone> Default for PersistentTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord + Clone> PersistentTree<T> {
+    pub fn new() -> Self {
+        PersistentTree { root: None }
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn contains(&self, key: &T) -> bool {
+        let mut current = &self.root;
+        while let Some(node) = current {
+            match key.cmp(&node.element) {
+                Ordering::Less => current = &node.left,
+                Ordering::Greater => current = &node.right,
+                Ordering::Equal => return true,
+            }
+        }
+        false
+    }
+
+    pub fn insert(&self, element: T) -> Self {
+        fn insert_recursive<T: Ord + Clone>(node: &Option<Arc<Node<T>>>, element: T) -> Arc<Node<T>> {
+            let Some(x) = node else {
+                return make(None, element, None);
+            };
+            if element <= x.element {
+                balance(Some(insert_recursive(&x.left, element)), x.element.clone(), x.right.clone())
+            } else {
+                balance(x.left.clone(), x.element.clone(), Some(insert_recursive(&x.right, element)))
+            }
+        }
+        PersistentTree { root: Some(insert_recursive(&self.root, element)) }
+    }
+
+    pub fn remove(&self, key: &T) -> Self {
+        fn remove_recursive<T: Ord + Clone>(node: &Option<Arc<Node<T>>>, key: &T) -> Option<Option<Arc<Node<T>>>> {
+            let x = node.as_ref()?;
+            let subtree = match key.cmp(&x.element) {
+                Ordering::Less => balance(remove_recursive(&x.left, key)?, x.element.clone(), x.right.clone()),
+                Ordering::Greater => balance(x.left.clone(), x.element.clone(), remove_recursive(&x.right, key)?),
+                Ordering::Equal => match (&x.left, &x.right) {
+                    (left, None) => return Some(left.clone()),
+                    (None, right) => return Some(right.clone()),
+                    (left, Some(right)) => {
+                        let (rest, min) = remove_min(right);
+                        balance(left.clone(), min, rest)
+                    }
+                },
+            };
+            Some(Some(subtree))
+        }
+        match remove_recursive(&self.root, key) {
+            Some(root) => PersistentTree { root },
+            None => self.clone(),
+        }
+    }
+
+    pub fn iter(&self) -> PersistentIter<'_, T> {
+        let mut iter = PersistentIter { stack: Vec::new() };
+        iter.push_left(&self.root);
+        iter
+    }
+}
+
+impl<T: Ord + Clone> FromIterator<T> for PersistentTree<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        iter.into_iter().fold(PersistentTree::new(), |tree, element| tree.insert(element))
+    }
+}
+
+impl<T: Debug + Ord + Clone> Debug for PersistentTree<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+pub struct PersistentIter<'a, T> {
+    stack: Vec<&'a Node<T>>,
+}
+
+impl<'a, T> PersistentIter<'a, T> {
+    fn push_left(&mut self, mut current: &'a Option<Arc<Node<T>>>) {
+        while let Some(node) = current {
+            self.stack.push(node);
+            current = &node.left;
+        }
+    }
+}
+
+impl<'a, T> Iterator for PersistentIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left(&node.right);
+        Some(&node.element)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_balanced<T>(node: &Option<Arc<Node<T>>>) -> usize {
+        let Some(x) = node else {
+            return 0;
+        };
+        let left = assert_balanced(&x.left);
+        let right = assert_balanced(&x.right);
+        assert!(left.abs_diff(right) <= 1);
+        assert_eq!(x.size, 1 + size(&x.left) + size(&x.right));
+        1 + left.max(right)
+    }
+
+    #[test]
+    fn test_old_versions_stay_valid() {
+        let empty = PersistentTree::new();
+        let v1 = empty.insert(2).insert(1).insert(3);
+        let v2 = v1.insert(4);
+        let v3 = v2.remove(&2);
+        let v4 = v3.remove(&10);
+
+        assert!(empty.is_empty());
+        assert_eq!(v1.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(v2.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+        assert_eq!(v3.iter().copied().collect::<Vec<_>>(), vec![1, 3, 4]);
+        assert!(v1.contains(&2) && !v3.contains(&2));
+        assert!(Arc::ptr_eq(v3.root.as_ref().unwrap(), v4.root.as_ref().unwrap()));
+        assert_eq!(format!("{:?}", v4), "{1, 3, 4}");
+    }
+
+    #[test]
+    fn test_shares_unchanged_subtrees() {
+        let tree: PersistentTree<i32> = (0..1000).collect();
+        assert_balanced(&tree.root);
+        let snapshot = tree.clone();
+        assert!(Arc::ptr_eq(tree.root.as_ref().unwrap(), snapshot.root.as_ref().unwrap()));
+
+        let updated = tree.insert(2000);
+        let (old, new) = (tree.root.as_ref().unwrap(), updated.root.as_ref().unwrap());
+        assert!(!Arc::ptr_eq(old, new));
+        assert!(Arc::ptr_eq(old.left.as_ref().unwrap(), new.left.as_ref().unwrap()));
+
+        let mut versions = vec![tree];
+        for i in (0..1000).step_by(7) {
+            let next = versions.last().unwrap().remove(&i);
+            versions.push(next);
+        }
+        let last = versions.last().unwrap();
+        assert_balanced(&last.root);
+        assert_eq!(last.len(), 1000 - 143);
+        assert_eq!(versions[0].len(), 1000);
+        assert_eq!(versions[1].len(), 999);
+    }
+}
@@ -0,0 +1,308 @@
+use std::fmt::{self, Display, Formatter};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Color {
+    Red,
+    Black,
+}
+
+struct Node<T> {
+    left: Option<Box<Node<T>>>,
+    element: T,
+    right: Option<Box<Node<T>>>,
+    color: Color,
+}
+
+fn is_red<T>(node: &Option<Box<Node<T>>>) -> bool {
+    matches!(node, Some(x) if x.color == Color::Red)
+}
+
+fn is_left_red<T>(node: &Option<Box<Node<T>>>) -> bool {
+    node.as_ref().is_some_and(|x| is_red(&x.left))
+}
+
+fn flip(color: &mut Color) {
+    *color = match color {
+        Color::Red => Color::Black,
+        Color::Black => Color::Red,
+    };
+}
+
+fn flip_colors<T>(node: &mut Node<T>) {
+    flip(&mut node.color);
+    for child in [&mut node.left, &mut node.right].into_iter().flatten() {
+        flip(&mut child.color);
+    }
+}
+
+fn rotate_left<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    let mut right = node.right.take().unwrap();
+    node.right = right.left.take();
+    right.color = node.color;
+    node.color = Color::Red;
+    right.left = Some(node);
+    right
+}
+
+fn rotate_right<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    let mut left = node.left.take().unwrap();
+    node.left = left.right.take();
+    left.color = node.color;
+    node.color = Color::Red;
+    left.right = Some(node);
+    left
+}
+
+fn fix_up<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    if is_red(&node.right) {
+        node = rotate_left(node);
+    }
+    if is_red(&node.left) && is_left_red(&node.left) {
+        node = rotate_right(node);
+    }
+    if is_red(&node.left) && is_red(&node.right) {
+        flip_colors(&mut node);
+    }
+    node
+}
+
+fn move_red_left<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    flip_colors(&mut node);
+    if is_left_red(&node.right) {
+        node.right = Some(rotate_right(node.right.take().unwrap()));
+        node = rotate_left(node);
+        flip_colors(&mut node);
+    }
+    node
+}
+
+fn move_red_right<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    flip_colors(&mut node);
+    if is_left_red(&node.left) {
+        node = rotate_right(node);
+        flip_colors(&mut node);
+    }
+    node
+}
+
+fn delete_min<T>(mut node: Box<Node<T>>) -> (Option<Box<Node<T>>>, T) {
+    if node.left.is_none() {
+        return (node.right.take(), node.element);
+    }
+    if !is_red(&node.left) && !is_left_red(&node.left) {
+        node = move_red_left(node);
+    }
+    let (left, min) = delete_min(node.left.take().unwrap());
+    node.left = left;
+    (Some(fix_up(node)), min)
+}
+
+fn delete_max<T>(mut node: Box<Node<T>>) -> (Option<Box<Node<T>>>, T) {
+    if is_red(&node.left) {
+        node = rotate_right(node);
+    }
+    if node.right.is_none() {
+        return (node.left.take(), node.element);
+    }
+    if !is_red(&node.right) && !is_left_red(&node.right) {
+        node = move_red_right(node);
+    }
+    let (right, max) = delete_max(node.right.take().unwrap());
+    node.right = right;
+    (Some(fix_up(node)), max)
+}
+
+fn delete<T: Ord>(mut node: Box<Node<T>>, key: &T) -> Option<Box<Node<T>>> {
+    if key < &node.element {
+        if !is_red(&node.left) && !is_left_red(&node.left) {
+            node = move_red_left(node);
+        }
+        node.left = delete(node.left.take().unwrap(), key);
+    } else {
+        if is_red(&node.left) {
+            node = rotate_right(node);
+        }
+        if key == &node.element && node.right.is_none() {
+            return node.left.take();
+        }
+        let mut found = key == &node.element;
+        if !is_red(&node.right) && !is_left_red(&node.right) {
+            if is_left_red(&node.left) {
+                found = false;
+            }
+            node = move_red_right(node);
+        }
+        if found {
+            let (right, min) = delete_min(node.right.take().unwrap());
+            node.right = right;
+            node.element = min;
+        } else {
+            node.right = delete(node.right.take().unwrap(), key);
+        }
+    }
+    Some(fix_up(node))
+}
+
+pub struct RbTree<T> {
+    root: Option<Box<Node<T>>>,
+}
+
+impl<T: Ord> Default for RbTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> RbTree<T> {
+    pub fn new() -> Self {
+        RbTree { root: None }
+    }
+
+    fn contains(&self, key: &T) -> bool {
+        let mut current = &self.root;
+        while let Some(x) = current {
+            if key < &x.element {
+                current = &x.left;
+            } else if key > &x.element {
+                current = &x.right;
+            } else {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn paint_root_red(&mut self) {
+        if let Some(root) = &mut self.root
+            && !is_red(&root.left)
+            && !is_red(&root.right)
+        {
+            root.color = Color::Red;
+        }
+    }
+
+    fn paint_root_black(&mut self) {
+        if let Some(root) = &mut self.root {
+            root.color = Color::Black;
+        }
+    }
+
+    pub fn insert(&mut self, element: T) {
+        fn insert_recursive<T: Ord>(node: Option<Box<Node<T>>>, element: T) -> Box<Node<T>> {
+            let Some(mut x) = node else {
+                return Box::new(Node { left: None, element, right: None, color: Color::Red });
+            };
+            if element <= x.element {
+                x.left = Some(insert_recursive(x.left.take(), element));
+            } else {
+                x.right = Some(insert_recursive(x.right.take(), element));
+            }
+            fix_up(x)
+        }
+        self.root = Some(insert_recursive(self.root.take(), element));
+        self.paint_root_black();
+    }
+
+    pub fn pop_max(&mut self) -> Option<T> {
+        self.paint_root_red();
+        let (root, max) = delete_max(self.root.take()?);
+        self.root = root;
+        self.paint_root_black();
+        Some(max)
+    }
+
+    pub fn remove(&mut self, key: &T) -> bool {
+        if !self.contains(key) {
+            return false;
+        }
+        self.paint_root_red();
+        self.root = delete(self.root.take().unwrap(), key);
+        self.paint_root_black();
+        true
+    }
+
+    pub fn inorder(&self) -> Vec<&T> {
+        self.iter().collect()
+    }
+
+    pub fn iter(&self) -> RbTreeIter<'_, T> {
+        let mut iter = RbTreeIter { stack: Vec::new() };
+        iter.push_left(&self.root);
+        iter
+    }
+}
+
+impl<T: Display + Ord> Display for RbTree<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fn fmt_node<T: Display>(node: &Option<Box<Node<T>>>, f: &mut Formatter<'_>, depth: usize) -> fmt::Result {
+            if let Some(x) = node {
+                fmt_node(&x.right, f, depth + 1)?;
+                for _ in 0..depth {
+                    write!(f, "    ")?;
+                }
+                writeln!(f, "{}", x.element)?;
+                fmt_node(&x.left, f, depth + 1)?;
+            }
+            Ok(())
+        }
+        fmt_node(&self.root, f, 0)
+    }
+}
+
+pub struct RbTreeIter<'a, T> {
+    stack: Vec<&'a Node<T>>,
+}
+
+impl<'a, T> RbTreeIter<'a, T> {
+    fn push_left(&mut self, mut current: &'a Option<Box<Node<T>>>) {
+        while let Some(node) = current {
+            self.stack.push(node);
+            current = &node.left;
+        }
+    }
+}
+
+impl<'a, T> Iterator for RbTreeIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left(&node.right);
+        Some(&node.element)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn black_height<T: Ord>(node: &Option<Box<Node<T>>>) -> usize {
+        let Some(x) = node else {
+            return 1;
+        };
+        assert!(!is_red(&x.right));
+        if x.color == Color::Red {
+            assert!(!is_red(&x.left));
+        }
+        let left = black_height(&x.left);
+        assert_eq!(left, black_height(&x.right));
+        left + usize::from(x.color == Color::Black)
+    }
+
+    #[test]
+    fn test_keeps_red_black_invariants() {
+        let mut tree = RbTree::new();
+        for i in 0..1000 {
+            tree.insert(i * 7 % 1000);
+            tree.insert(i % 10);
+        }
+        assert!(!is_red(&tree.root));
+        black_height(&tree.root);
+        for i in (0..500).step_by(3) {
+            assert!(tree.remove(&i));
+            tree.pop_max();
+            black_height(&tree.root);
+        }
+        assert!(!is_red(&tree.root));
+    }
+}
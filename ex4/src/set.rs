@@ -0,0 +1,255 @@
+use std::cmp::Ordering;
+use std::iter::Peekable;
+use std::mem;
+use std::ops::{BitAnd, BitOr, BitXor, Sub};
+
+use crate::{Tree, TreeIter};
+
+impl<T: Ord> Tree<T> {
+    pub fn union<'a>(&'a self, other: &'a Tree<T>) -> Union<'a, T> {
+        Union { a: self.iter().peekable(), b: other.iter().peekable() }
+    }
+
+    pub fn intersection<'a>(&'a self, other: &'a Tree<T>) -> Intersection<'a, T> {
+        Intersection { a: self.iter().peekable(), b: other.iter().peekable() }
+    }
+
+    pub fn difference<'a>(&'a self, other: &'a Tree<T>) -> Difference<'a, T> {
+        Difference { a: self.iter().peekable(), b: other.iter().peekable() }
+    }
+
+    pub fn symmetric_difference<'a>(&'a self, other: &'a Tree<T>) -> SymmetricDifference<'a, T> {
+        SymmetricDifference { a: self.iter().peekable(), b: other.iter().peekable() }
+    }
+
+    pub fn is_subset(&self, other: &Tree<T>) -> bool {
+        self.len() <= other.len() && self.difference(other).next().is_none()
+    }
+
+    pub fn is_superset(&self, other: &Tree<T>) -> bool {
+        other.is_subset(self)
+    }
+
+    pub fn is_disjoint(&self, other: &Tree<T>) -> bool {
+        self.intersection(other).next().is_none()
+    }
+
+    pub fn split_off(&mut self, key: &T) -> Tree<T> {
+        let (below, rest): (Vec<T>, Vec<T>) = mem::take(self).into_iter().partition(|x| x < key);
+        *self = Tree::from_sorted(below);
+        Tree::from_sorted(rest)
+    }
+
+    pub fn append(&mut self, other: &mut Tree<T>) {
+        let mut merged = Vec::with_capacity(self.len() + other.len());
+        let mut a = mem::take(self).into_iter().peekable();
+        let mut b = mem::take(other).into_iter().peekable();
+        while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+            merged.push(if x <= y { a.next() } else { b.next() }.unwrap());
+        }
+        merged.extend(a);
+        merged.extend(b);
+        *self = Tree::from_sorted(merged);
+    }
+}
+
+fn compare<T: Ord>(a: &mut Peekable<TreeIter<'_, T>>, b: &mut Peekable<TreeIter<'_, T>>) -> Option<Ordering> {
+    match (a.peek(), b.peek()) {
+        (Some(x), Some(y)) => Some(x.cmp(y)),
+        (Some(_), None) => Some(Ordering::Less),
+        (None, Some(_)) => Some(Ordering::Greater),
+        (None, None) => None,
+    }
+}
+
+pub struct Union<'a, T> {
+    a: Peekable<TreeIter<'a, T>>,
+    b: Peekable<TreeIter<'a, T>>,
+}
+
+impl<'a, T: Ord> Iterator for Union<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match compare(&mut self.a, &mut self.b)? {
+            Ordering::Less => self.a.next(),
+            Ordering::Greater => self.b.next(),
+            Ordering::Equal => {
+                self.b.next();
+                self.a.next()
+            }
+        }
+    }
+}
+
+pub struct Intersection<'a, T> {
+    a: Peekable<TreeIter<'a, T>>,
+    b: Peekable<TreeIter<'a, T>>,
+}
+
+impl<'a, T: Ord> Iterator for Intersection<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match (self.a.peek(), self.b.peek()) {
+                (Some(x), Some(y)) => match x.cmp(y) {
+                    Ordering::Less => {
+                        self.a.next();
+                    }
+                    Ordering::Greater => {
+                        self.b.next();
+                    }
+                    Ordering::Equal => {
+                        self.b.next();
+                        return self.a.next();
+                    }
+                },
+                _ => return None,
+            }
+        }
+    }
+}
+
+pub struct Difference<'a, T> {
+    a: Peekable<TreeIter<'a, T>>,
+    b: Peekable<TreeIter<'a, T>>,
+}
+
+impl<'a, T: Ord> Iterator for Difference<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let x = self.a.peek()?;
+            match self.b.peek().map(|y| x.cmp(y)) {
+                None | Some(Ordering::Less) => return self.a.next(),
+                Some(Ordering::Greater) => {
+                    self.b.next();
+                }
+                Some(Ordering::Equal) => {
+                    self.a.next();
+                    self.b.next();
+                }
+            }
+        }
+    }
+}
+
+pub struct SymmetricDifference<'a, T> {
+    a: Peekable<TreeIter<'a, T>>,
+    b: Peekable<TreeIter<'a, T>>,
+}
+
+impl<'a, T: Ord> Iterator for SymmetricDifference<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match compare(&mut self.a, &mut self.b)? {
+                Ordering::Less => return self.a.next(),
+                Ordering::Greater => return self.b.next(),
+                Ordering::Equal => {
+                    self.a.next();
+                    self.b.next();
+                }
+            }
+        }
+    }
+}
+
+impl<T: Ord + Clone> BitOr<&Tree<T>> for &Tree<T> {
+    type Output = Tree<T>;
+
+    fn bitor(self, other: &Tree<T>) -> Tree<T> {
+        Tree::from_sorted(self.union(other).cloned().collect())
+    }
+}
+
+impl<T: Ord + Clone> BitAnd<&Tree<T>> for &Tree<T> {
+    type Output = Tree<T>;
+
+    fn bitand(self, other: &Tree<T>) -> Tree<T> {
+        Tree::from_sorted(self.intersection(other).cloned().collect())
+    }
+}
+
+impl<T: Ord + Clone> Sub<&Tree<T>> for &Tree<T> {
+    type Output = Tree<T>;
+
+    fn sub(self, other: &Tree<T>) -> Tree<T> {
+        Tree::from_sorted(self.difference(other).cloned().collect())
+    }
+}
+
+impl<T: Ord + Clone> BitXor<&Tree<T>> for &Tree<T> {
+    type Output = Tree<T>;
+
+    fn bitxor(self, other: &Tree<T>) -> Tree<T> {
+        Tree::from_sorted(self.symmetric_difference(other).cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree(elements: &[i32]) -> Tree<i32> {
+        elements.iter().copied().collect()
+    }
+
+    fn collect<'a>(iter: impl Iterator<Item = &'a i32>) -> Vec<i32> {
+        iter.copied().collect()
+    }
+
+    #[test]
+    fn test_lazy_operations() {
+        let a = tree(&[1, 2, 3, 5, 8]);
+        let b = tree(&[2, 3, 4, 8, 9]);
+        assert_eq!(collect(a.union(&b)), vec![1, 2, 3, 4, 5, 8, 9]);
+        assert_eq!(collect(a.intersection(&b)), vec![2, 3, 8]);
+        assert_eq!(collect(a.difference(&b)), vec![1, 5]);
+        assert_eq!(collect(b.difference(&a)), vec![4, 9]);
+        assert_eq!(collect(a.symmetric_difference(&b)), vec![1, 4, 5, 9]);
+        assert_eq!(collect(a.union(&Tree::new())), vec![1, 2, 3, 5, 8]);
+        assert!(a.intersection(&Tree::new()).next().is_none());
+    }
+
+    #[test]
+    fn test_owned_operations() {
+        let a = tree(&[1, 2, 3]);
+        let b = tree(&[3, 4]);
+        assert_eq!(&a | &b, tree(&[1, 2, 3, 4]));
+        assert_eq!(&a & &b, tree(&[3]));
+        assert_eq!(&a - &b, tree(&[1, 2]));
+        assert_eq!(&a ^ &b, tree(&[1, 2, 4]));
+    }
+
+    #[test]
+    fn test_relations() {
+        let a = tree(&[1, 2, 3]);
+        assert!(tree(&[1, 3]).is_subset(&a));
+        assert!(!tree(&[1, 4]).is_subset(&a));
+        assert!(a.is_superset(&tree(&[2])));
+        assert!(Tree::new().is_subset(&a));
+        assert!(a.is_disjoint(&tree(&[4, 5])));
+        assert!(!a.is_disjoint(&tree(&[3, 4])));
+    }
+
+    #[test]
+    fn test_split_off_and_append() {
+        let mut a: Tree<i32> = (0..100).collect();
+        let mut b = a.split_off(&40);
+        assert_eq!(a, (0..40).collect());
+        assert_eq!(b, (40..100).collect());
+        assert_eq!(a.split_off(&1000), Tree::new());
+
+        let mut c: Tree<i32> = (35..45).collect();
+        a.append(&mut c);
+        a.append(&mut b);
+        assert!(c.is_empty() && b.is_empty());
+        assert_eq!(a.len(), 110);
+        assert!(a.iter().zip(a.iter().skip(1)).all(|(x, y)| x <= y));
+        assert_eq!(a.range(35..45).count(), 20);
+    }
+}
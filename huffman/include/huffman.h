@@ -0,0 +1,81 @@
+/* api em C da biblioteca huffman (libhuffman.so / libhuffman.a).
+ * gerado pelo cbindgen a partir de src/ffi.rs: pra atualizar, rode
+ * HUFFMAN_UPDATE_HEADER=1 cargo test --test header */
+
+#ifndef HUFFMAN_H
+#define HUFFMAN_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+/*
+ sucesso
+ */
+#define HUFFMAN_OK 0
+
+/*
+ ponteiro nulo onde não podia
+ */
+#define HUFFMAN_ERR_NULL 1
+
+/*
+ entrada não é um arquivo válido
+ */
+#define HUFFMAN_ERR_INVALID_DATA 2
+
+/*
+ entrada acabou antes do fim dos dados
+ */
+#define HUFFMAN_ERR_TRUNCATED 3
+
+/*
+ erro inesperado (panic) dentro da biblioteca
+ */
+#define HUFFMAN_ERR_INTERNAL 4
+
+/*
+ entrada válida, mas que esta api não lê (arquivo cifrado, que precisa de senha)
+ */
+#define HUFFMAN_ERR_UNSUPPORTED 5
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/*
+ comprime input[0..input_len]. em caso de sucesso *output aponta pra um buffer
+ de *output_len bytes alocado pela biblioteca, que deve ser liberado com huffman_free.
+ em caso de erro *output fica NULL e *output_len fica 0.
+
+ # Safety
+ `input` precisa apontar pra `input_len` bytes legíveis; `output` e `output_len` precisam ser válidos.
+ */
+int32_t huffman_compress(const uint8_t *input,
+                         size_t input_len,
+                         uint8_t **output,
+                         size_t *output_len);
+
+/*
+ descomprime input[0..input_len]; mesmas regras de alocação de huffman_compress.
+
+ # Safety
+ `input` precisa apontar pra `input_len` bytes legíveis; `output` e `output_len` precisam ser válidos.
+ */
+int32_t huffman_decompress(const uint8_t *input,
+                           size_t input_len,
+                           uint8_t **output,
+                           size_t *output_len);
+
+/*
+ libera um buffer devolvido por huffman_compress/huffman_decompress (NULL é ignorado).
+
+ # Safety
+ `buffer` e `len` precisam ser exatamente os devolvidos por `huffman_compress`/`huffman_decompress`.
+ */
+void huffman_free(uint8_t *buffer, size_t len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* HUFFMAN_H */
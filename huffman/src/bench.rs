@@ -0,0 +1,247 @@
+// benchmark de um diretório de arquivos de exemplo (texto, binário, já comprimido...)
+// com todos os modos disponíveis; gera um relatório em CSV e em Markdown pra comparar
+// entre versões do codificador. corpus/ tem um conjunto pequeno pra isso.
+use std::borrow::Cow;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::format::{CompressOptions, DecompressOptions, compress_with, decompress_with};
+use crate::progress::Hooks;
+
+// cada medida é a menor de RUNS execuções, pra reduzir ruído
+const RUNS: usize = 3;
+const BENCH_PASSWORD: &str = "benchmark";
+
+pub struct BenchResult {
+    pub file: String,
+    pub mode: &'static str,
+    pub original: u64,
+    pub compressed: u64,
+    pub compress_time: Duration,
+    pub decompress_time: Duration,
+}
+
+impl BenchResult {
+    pub fn ratio(&self) -> f64 {
+        if self.original == 0 { 1.0 } else { self.compressed as f64 / self.original as f64 }
+    }
+
+    pub fn compress_mib_s(&self) -> f64 {
+        mib_per_s(self.original, self.compress_time)
+    }
+
+    pub fn decompress_mib_s(&self) -> f64 {
+        mib_per_s(self.original, self.decompress_time)
+    }
+}
+
+fn mib_per_s(bytes: u64, time: Duration) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0) / time.as_secs_f64().max(1e-9)
+}
+
+fn modes() -> Vec<(&'static str, CompressOptions, DecompressOptions)> {
+    let default = CompressOptions::default();
+    let password = Some(String::from(BENCH_PASSWORD));
+    vec![
+        ("padrão", default.clone(), DecompressOptions::default()),
+        ("1 thread", CompressOptions { threads: 1, ..default.clone() }, DecompressOptions::default()),
+        ("eof", CompressOptions { eof_symbol: true, ..default.clone() }, DecompressOptions::default()),
+        (
+            "mmap",
+            CompressOptions { mmap: true, ..default.clone() },
+            DecompressOptions { mmap: true, ..DecompressOptions::default() },
+        ),
+        (
+            "cifrado",
+            CompressOptions { password: password.clone(), ..default },
+            DecompressOptions { password, ..DecompressOptions::default() },
+        ),
+    ]
+}
+
+// diretório temporário apagado no drop, inclusive quando algo dá errado no meio do caminho.
+// o contador separa os diretórios de benchmarks (ou testes) rodando ao mesmo tempo.
+pub(crate) struct ScratchDir(pub(crate) PathBuf);
+
+impl ScratchDir {
+    pub(crate) fn new(name: &str) -> io::Result<Self> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let id = NEXT.fetch_add(1, Ordering::Relaxed);
+        let dir = std::env::temp_dir().join(format!("huffman-{}-{}-{}", name, std::process::id(), id));
+        fs::create_dir_all(&dir)?;
+        Ok(ScratchDir(dir))
+    }
+
+    pub(crate) fn join(&self, name: &str) -> PathBuf {
+        self.0.join(name)
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn timed(f: impl Fn() -> io::Result<()>) -> io::Result<Duration> {
+    let mut best = Duration::MAX;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        f()?;
+        best = best.min(start.elapsed());
+    }
+    Ok(best)
+}
+
+// roda todos os modos em todos os arquivos de `dir` (sem subdiretórios), em ordem de nome
+pub fn run_benchmark(dir: &Path) -> io::Result<Vec<BenchResult>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<_>>()?;
+    files.retain(|path| path.is_file());
+    files.sort();
+
+    let scratch = ScratchDir::new("bench")?;
+    let packed = scratch.join("packed.bin");
+    let unpacked = scratch.join("unpacked");
+    let path = |p: &Path| p.to_string_lossy().into_owned();
+
+    let mut results = Vec::new();
+    for file in &files {
+        let original = fs::read(file)?;
+        for (mode, compress_options, decompress_options) in modes() {
+            let compress_time = timed(|| {
+                compress_with(&path(file), &path(&packed), &compress_options, &mut Hooks::default())
+            })?;
+            let decompress_time = timed(|| {
+                decompress_with(&path(&packed), &path(&unpacked), &decompress_options, &mut Hooks::default())
+            })?;
+            if fs::read(&unpacked)? != original {
+                return Err(io::Error::other(format!("{}: modo {} não voltou igual", file.display(), mode)));
+            }
+
+            results.push(BenchResult {
+                file: file.file_name().unwrap().to_string_lossy().into_owned(),
+                mode,
+                original: original.len() as u64,
+                compressed: fs::metadata(&packed)?.len(),
+                compress_time,
+                decompress_time,
+            });
+        }
+    }
+    Ok(results)
+}
+
+// campos com vírgula, aspas ou quebra de linha vão entre aspas, com as aspas duplicadas
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+pub fn write_csv(results: &[BenchResult], out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "arquivo,modo,original,comprimido,razao,compressao_mib_s,descompressao_mib_s")?;
+    for r in results {
+        writeln!(
+            out,
+            "{},{},{},{},{:.4},{:.2},{:.2}",
+            csv_field(&r.file),
+            csv_field(r.mode),
+            r.original,
+            r.compressed,
+            r.ratio(),
+            r.compress_mib_s(),
+            r.decompress_mib_s()
+        )?;
+    }
+    Ok(())
+}
+
+pub fn write_markdown(results: &[BenchResult], out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "| arquivo | modo | original | comprimido | razão | compressão (MiB/s) | descompressão (MiB/s) |")?;
+    writeln!(out, "|---|---|---:|---:|---:|---:|---:|")?;
+    for r in results {
+        writeln!(
+            out,
+            "| {} | {} | {} | {} | {:.1}% | {:.2} | {:.2} |",
+            r.file,
+            r.mode,
+            r.original,
+            r.compressed,
+            r.ratio() * 100.0,
+            r.compress_mib_s(),
+            r.decompress_mib_s()
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_benchmark_report() {
+        let dir = ScratchDir::new("corpus").unwrap();
+        fs::write(dir.join("a.txt"), "abracadabra ".repeat(500)).unwrap();
+        fs::write(dir.join("b.bin"), (0..=255u8).cycle().take(2000).collect::<Vec<_>>()).unwrap();
+
+        let results = run_benchmark(&dir.0).unwrap();
+        assert_eq!(results.len(), 2 * modes().len());
+        assert!(results.iter().filter(|r| r.file == "a.txt").all(|r| r.ratio() < 0.5));
+
+        let mut csv = Vec::new();
+        write_csv(&results, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), results.len() + 1);
+        assert!(csv.contains("\na.txt,padrão,6000,"));
+
+        let mut md = Vec::new();
+        write_markdown(&results, &mut md).unwrap();
+        assert!(String::from_utf8(md).unwrap().contains("| b.bin | cifrado | 2000 |"));
+    }
+
+    #[test]
+    fn test_csv_quotes_fields() {
+        let result = BenchResult {
+            file: String::from("dados, \"brutos\".txt"),
+            mode: "padrão",
+            original: 10,
+            compressed: 5,
+            compress_time: Duration::from_secs(1),
+            decompress_time: Duration::from_secs(1),
+        };
+        let mut csv = Vec::new();
+        write_csv(&[result], &mut csv).unwrap();
+        let line = String::from_utf8(csv).unwrap().lines().nth(1).unwrap().to_string();
+        assert_eq!(line, "\"dados, \"\"brutos\"\".txt\",padrão,10,5,0.5000,0.00,0.00");
+    }
+
+    #[test]
+    fn test_scratch_dir_is_removed_on_panic() {
+        let scratch = ScratchDir::new("guard").unwrap();
+        let path = scratch.0.clone();
+        let result = std::panic::catch_unwind(move || {
+            fs::write(scratch.join("x"), b"x").unwrap();
+            panic!("falhou no meio");
+        });
+        assert!(result.is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_sample_corpus() {
+        let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("corpus");
+        let results = run_benchmark(&corpus).unwrap();
+        let files: Vec<&str> = results.iter().map(|r| r.file.as_str()).collect();
+        assert!(["binario.bin", "comprimido.gz", "texto.txt"].iter().all(|f| files.contains(f)));
+        // o que já vem comprimido não encolhe
+        assert!(results.iter().filter(|r| r.file == "comprimido.gz").all(|r| r.ratio() >= 1.0));
+    }
+}
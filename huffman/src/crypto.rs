@@ -0,0 +1,219 @@
+// envelope cifrado: o stream comprimido inteiro (já no formato normal) é dividido em
+// pedaços de CHUNK_SIZE bytes, cada um cifrado com ChaCha20-Poly1305. a chave vem da
+// senha via Argon2id. o cabeçalho (parâmetros, sal e prefixo do nonce) é autenticado
+// como dado associado de todos os pedaços, então qualquer alteração nele é detectada.
+//
+// nonce de cada pedaço: prefixo aleatório (7 bytes) + contador u32 + flag de último pedaço,
+// o que impede reordenar, repetir ou truncar pedaços.
+//
+// cada pedaço vem precedido pelo tamanho do texto claro em varint, então o envelope
+// termina sozinho e pode ser seguido por outro membro.
+use std::io::{self, Read, Write};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+use crate::format::{invalid_data, read_u8, read_varint, write_varint};
+
+const KDF_ARGON2ID: u8 = 1;
+// parâmetros recomendados pela OWASP pro Argon2id: 19 MiB, 2 passadas, 1 thread
+const M_COST: u32 = 19 * 1024;
+const T_COST: u32 = 2;
+const P_COST: u32 = 1;
+// limites na leitura, pouco acima do que se grava: o cabeçalho só é autenticado depois da
+// derivação, então um arquivo adulterado não pode pedir memória ou tempo absurdos
+const MAX_M_COST: u64 = 64 * 1024;
+const MAX_T_COST: u64 = 4;
+const MAX_P_COST: u64 = 4;
+
+const SALT_LEN: usize = 16;
+const NONCE_PREFIX_LEN: usize = 7;
+const TAG_LEN: usize = 16;
+const CHUNK_SIZE: usize = 64 * 1024;
+
+fn derive_key(password: &str, salt: &[u8], m_cost: u32, t_cost: u32, p_cost: u32) -> io::Result<ChaCha20Poly1305> {
+    let params = Params::new(m_cost, t_cost, p_cost, Some(32)).map_err(|_| invalid_data("parâmetros de chave inválidos"))?;
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|_| invalid_data("parâmetros de chave inválidos"))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+fn chunk_nonce(prefix: &[u8; NONCE_PREFIX_LEN], counter: u32, last: bool) -> Nonce {
+    let mut nonce = [0u8; 12];
+    nonce[..NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[NONCE_PREFIX_LEN..11].copy_from_slice(&counter.to_be_bytes());
+    nonce[11] = last as u8;
+    nonce.into()
+}
+
+pub(crate) struct EncryptWriter<W: Write> {
+    inner: W,
+    cipher: ChaCha20Poly1305,
+    header: Vec<u8>,
+    nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    counter: u32,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> EncryptWriter<W> {
+    // gera sal e nonce, deriva a chave e grava o cabeçalho (que começa com `magic`)
+    pub(crate) fn new(mut inner: W, password: &str, magic: &[u8]) -> io::Result<Self> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce_prefix = [0u8; NONCE_PREFIX_LEN];
+        getrandom::getrandom(&mut salt).map_err(io::Error::other)?;
+        getrandom::getrandom(&mut nonce_prefix).map_err(io::Error::other)?;
+
+        let mut header = magic.to_vec();
+        header.push(KDF_ARGON2ID);
+        write_varint(&mut header, M_COST as u64);
+        write_varint(&mut header, T_COST as u64);
+        write_varint(&mut header, P_COST as u64);
+        header.extend_from_slice(&salt);
+        header.extend_from_slice(&nonce_prefix);
+        inner.write_all(&header)?;
+
+        let cipher = derive_key(password, &salt, M_COST, T_COST, P_COST)?;
+        Ok(EncryptWriter { inner, cipher, header, nonce_prefix, counter: 0, buffer: Vec::with_capacity(CHUNK_SIZE) })
+    }
+
+    fn write_chunk(&mut self, len: usize, last: bool) -> io::Result<()> {
+        let nonce = chunk_nonce(&self.nonce_prefix, self.counter, last);
+        let payload = Payload { msg: &self.buffer[..len], aad: &self.header };
+        let sealed = self.cipher.encrypt(&nonce, payload).map_err(|_| io::Error::other("falha ao cifrar"))?;
+        let mut frame = Vec::new();
+        write_varint(&mut frame, len as u64);
+        self.inner.write_all(&frame)?;
+        self.inner.write_all(&sealed)?;
+        self.buffer.drain(..len);
+        self.counter = self.counter.checked_add(1).ok_or_else(|| io::Error::other("arquivo grande demais pra cifrar"))?;
+        Ok(())
+    }
+
+    // o último pedaço sempre tem menos que CHUNK_SIZE bytes (pode ser vazio), assim o
+    // leitor sabe qual é o último só pelo tamanho
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        while self.buffer.len() >= CHUNK_SIZE {
+            self.write_chunk(CHUNK_SIZE, false)?;
+        }
+        self.write_chunk(self.buffer.len(), true)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for EncryptWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        // guarda sempre algo no buffer: só finish sabe qual pedaço é o último
+        while self.buffer.len() > CHUNK_SIZE {
+            self.write_chunk(CHUNK_SIZE, false)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+pub(crate) struct DecryptReader<R: Read> {
+    inner: R,
+    cipher: ChaCha20Poly1305,
+    header: Vec<u8>,
+    nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    counter: u32,
+    plain: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl<R: Read> DecryptReader<R> {
+    // lê o resto do cabeçalho (o `magic` já foi lido por quem chamou) e deriva a chave
+    pub(crate) fn new(mut inner: R, password: &str, magic: &[u8]) -> io::Result<Self> {
+        let mut header = magic.to_vec();
+        if read_u8(&mut inner)? != KDF_ARGON2ID {
+            return Err(invalid_data("derivação de chave desconhecida"));
+        }
+        header.push(KDF_ARGON2ID);
+
+        let mut costs = [0u32; 3];
+        for (cost, max) in costs.iter_mut().zip([MAX_M_COST, MAX_T_COST, MAX_P_COST]) {
+            let value = read_varint(&mut inner)?;
+            if value > max {
+                return Err(invalid_data("parâmetros de chave inválidos"));
+            }
+            write_varint(&mut header, value);
+            *cost = value as u32;
+        }
+
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce_prefix = [0u8; NONCE_PREFIX_LEN];
+        inner.read_exact(&mut salt)?;
+        inner.read_exact(&mut nonce_prefix)?;
+        header.extend_from_slice(&salt);
+        header.extend_from_slice(&nonce_prefix);
+
+        let [m_cost, t_cost, p_cost] = costs;
+        let cipher = derive_key(password, &salt, m_cost, t_cost, p_cost)?;
+        Ok(DecryptReader {
+            inner,
+            cipher,
+            header,
+            nonce_prefix,
+            counter: 0,
+            plain: Vec::new(),
+            pos: 0,
+            done: false,
+        })
+    }
+
+    fn next_chunk(&mut self) -> io::Result<()> {
+        let len = read_varint(&mut self.inner)?;
+        if len > CHUNK_SIZE as u64 {
+            return Err(invalid_data("pedaço cifrado grande demais"));
+        }
+
+        let mut sealed = vec![0u8; len as usize + TAG_LEN];
+        self.inner.read_exact(&mut sealed)?;
+        let last = len < CHUNK_SIZE as u64;
+
+        let nonce = chunk_nonce(&self.nonce_prefix, self.counter, last);
+        let payload = Payload { msg: &sealed, aad: &self.header };
+        self.plain = self
+            .cipher
+            .decrypt(&nonce, payload)
+            .map_err(|_| invalid_data("senha errada ou arquivo adulterado"))?;
+        self.pos = 0;
+        self.counter = self.counter.wrapping_add(1);
+        self.done = last;
+        Ok(())
+    }
+
+    // confere que o resto do stream (até o último pedaço) está íntegro e vazio
+    pub(crate) fn finish(mut self) -> io::Result<()> {
+        let mut rest = Vec::new();
+        self.read_to_end(&mut rest)?;
+        if !rest.is_empty() {
+            return Err(invalid_data("dados sobrando depois do fim"));
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecryptReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.plain.len() {
+            if self.done {
+                return Ok(0);
+            }
+            self.next_chunk()?;
+        }
+        let n = buf.len().min(self.plain.len() - self.pos);
+        buf[..n].copy_from_slice(&self.plain[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
@@ -0,0 +1,117 @@
+// modo "explain", pra aulas: mostra passo a passo a construção da árvore, a tabela de
+// códigos e como um texto curto vira bits e bytes.
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::huffman::{Huffman, build_codes, build_frequency_table, build_huffman_tree_with, encode};
+
+// descreve um nó: folha como 'a' (5), nó interno com todos os caracteres embaixo dele
+fn describe(node: &Huffman<char>) -> String {
+    fn leaves(node: &Huffman<char>, acc: &mut Vec<String>) {
+        match node {
+            Huffman::Leaf { symbol, .. } => acc.push(format!("{:?}", symbol)),
+            Huffman::Node { left, right, .. } => {
+                leaves(left, acc);
+                leaves(right, acc);
+            }
+        }
+    }
+    match node {
+        Huffman::Leaf { symbol, freq } => format!("{:?} ({})", symbol, freq),
+        Huffman::Node { freq, .. } => {
+            let mut acc = Vec::new();
+            leaves(node, &mut acc);
+            format!("{{{}}} ({})", acc.join(", "), freq)
+        }
+    }
+}
+
+// `pause` é chamado entre os passos (o CLI espera um Enter quando está num terminal)
+pub fn explain(text: &str, out: &mut impl Write, pause: &mut dyn FnMut()) -> io::Result<()> {
+    if text.is_empty() {
+        return writeln!(out, "texto vazio: nada pra codificar");
+    }
+
+    let freq_table = build_frequency_table(text.chars());
+    let mut symbols: Vec<_> = freq_table.iter().collect();
+    symbols.sort();
+    writeln!(out, "frequências:")?;
+    for (c, f) in &symbols {
+        writeln!(out, "  {:?} {}", c, f)?;
+    }
+    pause();
+
+    let mut merges = Vec::new();
+    let tree = build_huffman_tree_with(&freq_table, |min1, min2| {
+        merges.push((describe(min1), describe(min2), min1.get_freq() + min2.get_freq()))
+    });
+
+    writeln!(out, "\nconstrução da árvore (sempre tira os dois nós de menor frequência do heap):")?;
+    if merges.is_empty() {
+        writeln!(out, "  só um caractere: a árvore é a própria folha e o código dele é 0")?;
+    }
+    for (i, (min1, min2, freq)) in merges.iter().enumerate() {
+        writeln!(out, "  passo {}: tira {}", i + 1, min1)?;
+        writeln!(out, "           tira {}", min2)?;
+        writeln!(out, "           junta num nó de frequência {} (0 = esquerda, 1 = direita)", freq)?;
+        pause();
+    }
+
+    let mut codes = HashMap::new();
+    build_codes(&tree, String::new(), &mut codes);
+    let mut table: Vec<_> = codes.iter().collect();
+    table.sort_by_key(|&(c, code)| (code.len(), code.clone(), *c));
+    writeln!(out, "\ntabela de códigos:")?;
+    for (c, code) in &table {
+        writeln!(out, "  {:?} {:>6}  {}", c, freq_table[c], code)?;
+    }
+    pause();
+
+    writeln!(out, "\ncodificação de {:?}:", text)?;
+    for c in text.chars() {
+        writeln!(out, "  {:?} -> {}", c, codes[&c])?;
+    }
+
+    let bits = encode(text.chars(), &codes);
+    let padding = (8 - bits.len() % 8) % 8;
+    writeln!(out, "\nbits: {}", bits)?;
+    writeln!(out, "bytes:")?;
+    for chunk in bits.as_bytes().chunks(8) {
+        let chunk = std::str::from_utf8(chunk).unwrap();
+        let byte = u8::from_str_radix(&format!("{:0<8}", chunk), 2).unwrap();
+        if chunk.len() < 8 {
+            writeln!(out, "  {}|{}  0x{:02X}  ({} bits de enchimento)", chunk, "0".repeat(8 - chunk.len()), byte, padding)?;
+        } else {
+            writeln!(out, "  {}  0x{:02X}", chunk, byte)?;
+        }
+    }
+    writeln!(
+        out,
+        "\n{} caracteres ({} bytes utf-8) viram {} bits em {} bytes",
+        text.chars().count(),
+        text.len(),
+        bits.len(),
+        bits.len().div_ceil(8)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explain_output() {
+        let mut out = Vec::new();
+        let mut pauses = 0;
+        explain("aab", &mut out, &mut || pauses += 1).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.contains("passo 1: tira 'b' (1)"));
+        assert!(out.contains("tira 'a' (2)"));
+        assert!(out.contains("  'b'      1  0\n"));
+        assert!(out.contains("  'a'      2  1\n"));
+        assert!(out.contains("bits: 110"));
+        assert!(out.contains("  110|00000  0xC0  (5 bits de enchimento)"));
+        assert_eq!(pauses, 3);
+    }
+}
@@ -0,0 +1,141 @@
+// api em C pra compress/decompress sobre buffers. o cabeçalho include/huffman.h é gerado
+// a partir deste arquivo pelo cbindgen (tests/header.rs), então os comentários /// daqui vão parar nele.
+// a saída é alocada pela biblioteca e precisa ser liberada com huffman_free.
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+use crate::format::{compress_stream, decompress_stream};
+
+/// sucesso
+pub const HUFFMAN_OK: i32 = 0;
+/// ponteiro nulo onde não podia
+pub const HUFFMAN_ERR_NULL: i32 = 1;
+/// entrada não é um arquivo válido
+pub const HUFFMAN_ERR_INVALID_DATA: i32 = 2;
+/// entrada acabou antes do fim dos dados
+pub const HUFFMAN_ERR_TRUNCATED: i32 = 3;
+/// erro inesperado (panic) dentro da biblioteca
+pub const HUFFMAN_ERR_INTERNAL: i32 = 4;
+/// entrada válida, mas que esta api não lê (arquivo cifrado, que precisa de senha)
+pub const HUFFMAN_ERR_UNSUPPORTED: i32 = 5;
+
+fn error_code(e: &io::Error) -> i32 {
+    match e.kind() {
+        io::ErrorKind::InvalidData => HUFFMAN_ERR_INVALID_DATA,
+        io::ErrorKind::UnexpectedEof => HUFFMAN_ERR_TRUNCATED,
+        io::ErrorKind::InvalidInput => HUFFMAN_ERR_UNSUPPORTED,
+        _ => HUFFMAN_ERR_INTERNAL,
+    }
+}
+
+// roda a operação sobre o buffer de entrada e entrega o resultado como um buffer alocado aqui
+unsafe fn run(
+    input: *const u8,
+    input_len: usize,
+    output: *mut *mut u8,
+    output_len: *mut usize,
+    op: fn(&[u8], &mut Vec<u8>) -> io::Result<()>,
+) -> i32 {
+    if output.is_null() || output_len.is_null() || (input.is_null() && input_len > 0) {
+        return HUFFMAN_ERR_NULL;
+    }
+    unsafe {
+        *output = ptr::null_mut();
+        *output_len = 0;
+    }
+    let input = if input_len == 0 { &[][..] } else { unsafe { slice::from_raw_parts(input, input_len) } };
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut buffer = Vec::new();
+        op(input, &mut buffer).map(|_| buffer)
+    }));
+
+    match result {
+        Ok(Ok(buffer)) => {
+            let buffer = buffer.into_boxed_slice();
+            unsafe {
+                *output_len = buffer.len();
+                *output = Box::into_raw(buffer) as *mut u8;
+            }
+            HUFFMAN_OK
+        }
+        Ok(Err(e)) => error_code(&e),
+        Err(_) => HUFFMAN_ERR_INTERNAL,
+    }
+}
+
+/// comprime input[0..input_len]. em caso de sucesso *output aponta pra um buffer
+/// de *output_len bytes alocado pela biblioteca, que deve ser liberado com huffman_free.
+/// em caso de erro *output fica NULL e *output_len fica 0.
+///
+/// # Safety
+/// `input` precisa apontar pra `input_len` bytes legíveis; `output` e `output_len` precisam ser válidos.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn huffman_compress(
+    input: *const u8,
+    input_len: usize,
+    output: *mut *mut u8,
+    output_len: *mut usize,
+) -> i32 {
+    unsafe { run(input, input_len, output, output_len, |i, o| compress_stream(i, o)) }
+}
+
+/// descomprime input[0..input_len]; mesmas regras de alocação de huffman_compress.
+///
+/// # Safety
+/// `input` precisa apontar pra `input_len` bytes legíveis; `output` e `output_len` precisam ser válidos.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn huffman_decompress(
+    input: *const u8,
+    input_len: usize,
+    output: *mut *mut u8,
+    output_len: *mut usize,
+) -> i32 {
+    unsafe { run(input, input_len, output, output_len, |i, o| decompress_stream(i, o)) }
+}
+
+/// libera um buffer devolvido por huffman_compress/huffman_decompress (NULL é ignorado).
+///
+/// # Safety
+/// `buffer` e `len` precisam ser exatamente os devolvidos por `huffman_compress`/`huffman_decompress`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn huffman_free(buffer: *mut u8, len: usize) {
+    if !buffer.is_null() {
+        drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(buffer, len)) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CompressOptions, Hooks, compress_stream_with};
+
+    #[test]
+    fn test_ffi_roundtrip_and_errors() {
+        let text = b"mississippi mississippi mississippi";
+        let (mut compressed, mut compressed_len) = (ptr::null_mut(), 0);
+        let (mut output, mut output_len) = (ptr::null_mut(), 0);
+        unsafe {
+            assert_eq!(huffman_compress(text.as_ptr(), text.len(), &mut compressed, &mut compressed_len), HUFFMAN_OK);
+            assert_eq!(huffman_decompress(compressed, compressed_len, &mut output, &mut output_len), HUFFMAN_OK);
+            assert_eq!(slice::from_raw_parts(output, output_len), text);
+            huffman_free(output, output_len);
+
+            assert_eq!(huffman_decompress(compressed, 6, &mut output, &mut output_len), HUFFMAN_ERR_TRUNCATED);
+            assert!(output.is_null());
+            huffman_free(compressed, compressed_len);
+
+            assert_eq!(huffman_decompress(b"HUF\x09".as_ptr(), 4, &mut output, &mut output_len), HUFFMAN_ERR_INVALID_DATA);
+            assert_eq!(huffman_compress(text.as_ptr(), text.len(), ptr::null_mut(), &mut output_len), HUFFMAN_ERR_NULL);
+
+            // arquivo cifrado sem senha
+            let options = CompressOptions { password: Some(String::from("senha")), ..CompressOptions::default() };
+            let mut sealed = Vec::new();
+            compress_stream_with(&text[..], &mut sealed, &options, &mut Hooks::default()).unwrap();
+            assert_eq!(huffman_decompress(sealed.as_ptr(), sealed.len(), &mut output, &mut output_len), HUFFMAN_ERR_UNSUPPORTED);
+            assert!(output.is_null());
+        }
+    }
+}
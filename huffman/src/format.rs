@@ -0,0 +1,1036 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write, BufReader, BufWriter};
+use std::iter;
+use std::thread;
+
+use memmap2::Mmap;
+
+use crate::crypto::{DecryptReader, EncryptWriter};
+use crate::progress::Hooks;
+use crate::huffman::{
+    Huffman, Symbol, build_codes, build_huffman_tree, bits_to_bytes, bytes_to_bits, count_bytes_parallel,
+    count_chars_parallel, decode, encode,
+};
+
+// formato do container: "HUF" + versão, seguido dos blocos.
+// arquivos sem o magic são do formato antigo (só huffman, sem flag).
+// versão 1: um único modo + corpo até o fim do arquivo.
+// versão 2: sequência de blocos [modo][corpo] terminada por MODE_END.
+// versão 3: igual à 2, mas tamanhos, contagens e frequências são varints de 64 bits.
+// versão 4: igual à 3, mas cada bloco (e o MODE_END) começa com o marcador SYNC e termina com
+// o crc32 dos seus dados, o que permite achar e validar os blocos intactos de um arquivo danificado.
+// ENCRYPTED no lugar da versão: envelope cifrado (ver crypto.rs) com um container normal dentro.
+// um arquivo pode ter vários membros concatenados (append), lidos em sequência.
+pub(crate) const MAGIC: &[u8; 3] = b"HUF";
+pub(crate) const VERSION: u8 = 4;
+pub(crate) const ENCRYPTED: u8 = 0xED;
+pub(crate) const SYNC: &[u8; 8] = b"\xFFHUFSYNC";
+const MODE_STORED: u8 = 0;  // dados copiados sem compressão (v2+: precedidos do tamanho)
+const MODE_HUFFMAN: u8 = 1; // cabeçalho de frequências + bits codificados
+const MODE_TREE: u8 = 2;    // formato da árvore em pré-ordem + bits codificados (v3)
+const MODE_BYTES: u8 = 3;   // igual ao MODE_TREE, mas com bytes como símbolos (v3)
+const MODE_END: u8 = 0xFF;  // fim da sequência de blocos
+
+// bit somado ao modo (v3, MODE_TREE e MODE_BYTES): em vez de t, o bloco termina com um
+// símbolo de fim reservado, e a árvore vem colada nos bits codificados, sem completar o byte.
+const MODE_EOF: u8 = 0x80;
+
+// limite pra leitura da árvore: um bloco nunca gera códigos tão longos,
+// então passar disso só acontece com arquivo corrompido.
+const MAX_TREE_DEPTH: usize = 64;
+
+// cada bloco é codificado com sua própria tabela, então a memória usada
+// depende só desse tamanho e não do tamanho do arquivo.
+const BLOCK_SIZE: usize = 1 << 20;
+
+pub(crate) fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+pub(crate) fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+    let mut buffer = [0u8; 1];
+    reader.read_exact(&mut buffer)?;
+    Ok(buffer[0])
+}
+
+fn read_u16(reader: &mut impl Read) -> io::Result<u16> {
+    let mut buffer = [0u8; 2];
+    reader.read_exact(&mut buffer)?;
+    Ok(u16::from_be_bytes(buffer))
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut buffer = [0u8; 4];
+    reader.read_exact(&mut buffer)?;
+    Ok(u32::from_be_bytes(buffer))
+}
+
+// varint (LEB128): 7 bits por byte, bit mais alto indica que tem mais bytes
+pub(crate) fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+pub(crate) fn read_varint(reader: &mut impl Read) -> io::Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = read_u8(reader)?;
+        value |= ((byte & 0x7F) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid_data("varint maior que 64 bits"))
+}
+
+// até a versão 2 os tamanhos são de largura fixa; a partir da 3 são varints
+fn read_count(reader: &mut impl Read, version: u8) -> io::Result<u64> {
+    if version >= 3 { read_varint(reader) } else { read_u16(reader).map(u64::from) }
+}
+
+fn read_len(reader: &mut impl Read, version: u8) -> io::Result<u64> {
+    if version >= 3 { read_varint(reader) } else { read_u32(reader).map(u64::from) }
+}
+
+#[derive(Debug, Clone)]
+pub struct CompressOptions {
+    // termina cada bloco com o símbolo de fim em vez de gravar a quantidade de bits
+    pub eof_symbol: bool,
+    // threads usadas pra contar as frequências de cada bloco
+    pub threads: usize,
+    // cifra e autentica o resultado com uma chave derivada dessa senha
+    pub password: Option<String>,
+    // compress_with mapeia o arquivo de entrada na memória em vez de lê-lo
+    pub mmap: bool,
+    // compress_with acrescenta um novo membro ao fim do arquivo de saída em vez de sobrescrevê-lo
+    pub append: bool,
+}
+
+impl Default for CompressOptions {
+    fn default() -> Self {
+        CompressOptions {
+            eof_symbol: false,
+            threads: thread::available_parallelism().map_or(1, usize::from),
+            password: None,
+            mmap: false,
+            append: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DecompressOptions {
+    // senha dos arquivos cifrados
+    pub password: Option<String>,
+    // decompress_with mapeia o arquivo de entrada na memória em vez de lê-lo
+    pub mmap: bool,
+}
+
+// como cada tipo de símbolo grava e lê suas folhas no cabeçalho da árvore
+trait TreeSymbol: Symbol {
+    fn write_leaf(self, bits: &mut String);
+    fn read_leaf(bits: &mut BitReader<impl Read>) -> io::Result<Self>;
+}
+
+// caracteres vão como seus bytes utf-8 (1 a 4 bytes)
+impl TreeSymbol for char {
+    fn write_leaf(self, bits: &mut String) {
+        let mut buffer = [0u8; 4];
+        for byte in self.encode_utf8(&mut buffer).bytes() {
+            bits.push_str(&format!("{:08b}", byte));
+        }
+    }
+
+    fn read_leaf(bits: &mut BitReader<impl Read>) -> io::Result<Self> {
+        let mut buffer = [0u8; 4];
+        buffer[0] = bits.read_byte()?;
+        let len = match buffer[0].leading_ones() {
+            0 => 1,
+            n @ 2..=4 => n as usize,
+            _ => return Err(invalid_data("caractere inválido")),
+        };
+        for byte in &mut buffer[1..len] {
+            *byte = bits.read_byte()?;
+        }
+        let c = std::str::from_utf8(&buffer[..len])
+            .map_err(|_| invalid_data("caractere inválido"))?
+            .chars()
+            .next()
+            .unwrap();
+        Ok(c)
+    }
+}
+
+impl TreeSymbol for u8 {
+    fn write_leaf(self, bits: &mut String) {
+        bits.push_str(&format!("{:08b}", self));
+    }
+
+    fn read_leaf(bits: &mut BitReader<impl Read>) -> io::Result<Self> {
+        bits.read_byte()
+    }
+}
+
+// no modo MODE_EOF o símbolo de fim é None; cada folha ganha um bit dizendo se é ele
+impl<S: TreeSymbol> TreeSymbol for Option<S> {
+    fn write_leaf(self, bits: &mut String) {
+        match self {
+            None => bits.push('0'),
+            Some(symbol) => {
+                bits.push('1');
+                symbol.write_leaf(bits);
+            }
+        }
+    }
+
+    fn read_leaf(bits: &mut BitReader<impl Read>) -> io::Result<Self> {
+        if bits.read_bit()? { S::read_leaf(bits).map(Some) } else { Ok(None) }
+    }
+}
+
+// serializa a árvore em pré-ordem: 0 = nó interno (seguido dos filhos),
+// 1 = folha (seguida do símbolo)
+fn write_tree<S: TreeSymbol>(tree: &Huffman<S>, bits: &mut String) {
+    match tree {
+        Huffman::Leaf { symbol, .. } => {
+            bits.push('1');
+            symbol.write_leaf(bits);
+        }
+        Huffman::Node { left, right, .. } => {
+            bits.push('0');
+            write_tree(left, bits);
+            write_tree(right, bits);
+        }
+    }
+}
+
+// monta a árvore dos símbolos e devolve (bits da árvore, bits codificados)
+fn code_symbols<S: TreeSymbol>(freq_table: &HashMap<S, u64>, symbols: impl Iterator<Item = S>) -> (String, String) {
+    let huffman_tree = build_huffman_tree(freq_table);
+
+    let mut codes = HashMap::new();
+    build_codes(&huffman_tree, String::new(), &mut codes);
+
+    let mut tree_bits = String::new();
+    write_tree(&huffman_tree, &mut tree_bits);
+    (tree_bits, encode(symbols, &codes))
+}
+
+// gera t, a árvore (completada até o byte) e os bits codificados;
+// com eof_symbol, só a árvore e os bits terminados pelo símbolo de fim
+fn huffman_body<S: TreeSymbol>(
+    freq_table: HashMap<S, u64>,
+    symbols: impl Iterator<Item = S>,
+    eof_symbol: bool,
+) -> Vec<u8> {
+    if eof_symbol {
+        let freq_table = freq_table.into_iter().map(|(s, f)| (Some(s), f)).chain(iter::once((None, 1))).collect();
+        let (tree_bits, encoded) = code_symbols(&freq_table, symbols.map(Some).chain(iter::once(None)));
+        return bits_to_bytes(&(tree_bits + &encoded));
+    }
+
+    let (tree_bits, encoded) = code_symbols(&freq_table, symbols);
+
+    let mut body = Vec::new();
+    write_varint(&mut body, encoded.len() as u64); // quantidade de bits codificados
+    body.extend_from_slice(&bits_to_bytes(&tree_bits)); // o último chunk já sai completado com zeros
+    body.extend_from_slice(&bits_to_bytes(&encoded));
+    body
+}
+
+// blocos utf-8 são codificados por caractere e os outros por byte; se o resultado
+// não for menor que o bloco, grava os bytes crus, então cada bloco cresce no máximo 17 bytes
+// (marcador, modo, tamanho e crc).
+fn write_block(block: &[u8], writer: &mut impl Write, options: &CompressOptions) -> io::Result<()> {
+    let (mode, body) = match std::str::from_utf8(block) {
+        Ok(text) => {
+            let freq_table = count_chars_parallel(text, options.threads);
+            (MODE_TREE, huffman_body(freq_table, text.chars(), options.eof_symbol))
+        }
+        Err(_) => {
+            let freq_table = count_bytes_parallel(block, options.threads);
+            (MODE_BYTES, huffman_body(freq_table, block.iter().copied(), options.eof_symbol))
+        }
+    };
+    let mode = if options.eof_symbol { mode | MODE_EOF } else { mode };
+
+    writer.write_all(SYNC)?;
+    if body.len() < block.len() {
+        writer.write_all(&[mode])?;
+        writer.write_all(&body)?;
+    } else {
+        let mut header = vec![MODE_STORED];
+        write_varint(&mut header, block.len() as u64);
+        writer.write_all(&header)?;
+        writer.write_all(block)?;
+    }
+    writer.write_all(&crc32fast::hash(block).to_be_bytes())
+}
+
+fn write_end(writer: &mut impl Write) -> io::Result<()> {
+    writer.write_all(SYNC)?;
+    writer.write_all(&[MODE_END])
+}
+
+pub fn compress_stream(reader: impl Read, writer: impl Write) -> io::Result<()> {
+    compress_stream_with(reader, writer, &CompressOptions::default(), &mut Hooks::default())
+}
+
+pub fn compress_stream_with(
+    reader: impl Read,
+    writer: impl Write,
+    options: &CompressOptions,
+    hooks: &mut Hooks,
+) -> io::Result<()> {
+    seal(writer, options, |writer| write_container(reader, writer, options, hooks))
+}
+
+// grava o container direto ou, se tiver senha, dentro do envelope cifrado
+fn seal(
+    mut writer: impl Write,
+    options: &CompressOptions,
+    container: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> io::Result<()> {
+    match &options.password {
+        Some(password) => {
+            let mut encrypted = EncryptWriter::new(&mut writer, password, &[MAGIC[0], MAGIC[1], MAGIC[2], ENCRYPTED])?;
+            container(&mut encrypted)?;
+            encrypted.finish()?;
+        }
+        None => container(&mut writer)?,
+    }
+    writer.flush()
+}
+
+// tamanho do próximo bloco de `data`: até BLOCK_SIZE bytes, sem cortar um caractere utf-8 no meio
+fn block_len(data: &[u8]) -> usize {
+    if data.len() <= BLOCK_SIZE {
+        return data.len();
+    }
+    match std::str::from_utf8(&data[..BLOCK_SIZE]) {
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        _ => BLOCK_SIZE,
+    }
+}
+
+// igual ao write_container, mas os blocos são fatias da entrada inteira (mapeada) em vez de cópias
+fn write_mapped_container(
+    data: &[u8],
+    mut writer: impl Write,
+    options: &CompressOptions,
+    hooks: &mut Hooks,
+) -> io::Result<()> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[VERSION])?;
+
+    let mut done = 0;
+    while done < data.len() {
+        let block = &data[done..done + block_len(&data[done..])];
+        write_block(block, &mut writer, options)?;
+        done += block.len();
+        hooks.report(done as u64)?;
+    }
+
+    write_end(&mut writer)?;
+    writer.flush()
+}
+
+fn write_container(
+    mut reader: impl Read,
+    mut writer: impl Write,
+    options: &CompressOptions,
+    hooks: &mut Hooks,
+) -> io::Result<()> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[VERSION])?;
+
+    let mut block = Vec::with_capacity(BLOCK_SIZE);
+    let mut carry = Vec::new();
+    let mut done = 0u64;
+    loop {
+        block.clear();
+        block.append(&mut carry);
+        reader.by_ref().take((BLOCK_SIZE - block.len()) as u64).read_to_end(&mut block)?;
+        if block.is_empty() {
+            break;
+        }
+
+        // não corta um caractere utf-8 no meio: os bytes incompletos do fim vão pro próximo bloco
+        if block.len() == BLOCK_SIZE
+            && let Err(e) = std::str::from_utf8(&block)
+            && e.error_len().is_none()
+        {
+            carry = block.split_off(e.valid_up_to());
+        }
+
+        write_block(&block, &mut writer, options)?;
+        done += block.len() as u64;
+        hooks.report(done)?;
+    }
+
+    write_end(&mut writer)?;
+    writer.flush()
+}
+
+pub fn compress(input_path: &str, output_path: &str) -> io::Result<()> {
+    compress_with(input_path, output_path, &CompressOptions::default(), &mut Hooks::default())
+}
+
+pub fn compress_with(
+    input_path: &str,
+    output_path: &str,
+    options: &CompressOptions,
+    hooks: &mut Hooks,
+) -> io::Result<()> {
+    if options.append {
+        return append_member(input_path, output_path, options, hooks);
+    }
+
+    let input = File::open(input_path)?;
+    if hooks.total.is_none() {
+        hooks.total = Some(input.metadata()?.len());
+    }
+    let writer = BufWriter::new(File::create(output_path)?);
+
+    if options.mmap {
+        // segurança: o arquivo não pode ser alterado por outro processo enquanto está mapeado
+        let data = unsafe { Mmap::map(&input)? };
+        return seal(writer, options, |writer| write_mapped_container(&data, writer, options, hooks));
+    }
+    compress_stream_with(BufReader::new(input), writer, options, hooks)
+}
+
+// comprime o membro num arquivo temporário e só o acrescenta ao arquivo depois de pronto: um erro,
+// um cancelamento ou um Ctrl-C durante a compressão não deixam um membro truncado no fim.
+// se a cópia final falhar, o arquivo volta ao tamanho original.
+fn append_member(
+    input_path: &str,
+    output_path: &str,
+    options: &CompressOptions,
+    hooks: &mut Hooks,
+) -> io::Result<()> {
+    let temp_path = format!("{}.{}.tmp", output_path, std::process::id());
+    let temp_options = CompressOptions { append: false, ..options.clone() };
+    let result = compress_with(input_path, &temp_path, &temp_options, hooks).and_then(|_| {
+        let mut output = OpenOptions::new().append(true).create(true).open(output_path)?;
+        let original_len = output.metadata()?.len();
+        match io::copy(&mut File::open(&temp_path)?, &mut output) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                output.set_len(original_len)?;
+                Err(e)
+            }
+        }
+    });
+    let _ = fs::remove_file(&temp_path);
+    result
+}
+
+// lê os t bits codificados (completados até o byte) e decodifica com a árvore
+fn decode_payload<S: Symbol>(reader: &mut impl Read, t: u64, tree: &Huffman<S>) -> io::Result<Vec<S>> {
+    // lê com take em vez de alocar t/8 bytes de uma vez: um t corrompido não estoura a memória
+    let mut encoded_bytes = Vec::new();
+    let len = t.div_ceil(8);
+    if reader.by_ref().take(len).read_to_end(&mut encoded_bytes)? as u64 != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    let bits = bytes_to_bits(&encoded_bytes);
+    let useful_bits = &bits[..t as usize];
+
+    Ok(decode(useful_bits, tree))
+}
+
+// lê o cabeçalho de frequências e exatamente os bytes codificados de um corpo huffman
+fn decode_huffman_body(reader: &mut impl Read, version: u8) -> io::Result<String> {
+    let n = read_count(reader, version)?; // número de caracteres distintos
+    let t = read_len(reader, version)?;   // quantidade de bits codificados
+    if n == 0 {
+        return Err(invalid_data("tabela de frequências vazia"));
+    }
+
+    // a soma de todas as frequências é a raiz da árvore: se ela cabe em 64 bits, nenhum nó estoura
+    let mut freq_table = HashMap::new();
+    let mut total = 0u64;
+    for _ in 0..n {
+        let c = char::from_u32(read_u32(reader)?).ok_or_else(|| invalid_data("caractere inválido"))?;
+        let f = read_len(reader, version)?;
+        total = total.checked_add(f).ok_or_else(|| invalid_data("frequências grandes demais"))?;
+        freq_table.insert(c, f);
+    }
+
+    let huffman_tree = build_huffman_tree(&freq_table);
+    Ok(decode_payload(reader, t, &huffman_tree)?.into_iter().collect())
+}
+
+// lê bits um a um, do mais significativo pro menos, como bits_to_bytes grava
+struct BitReader<'a, R> {
+    reader: &'a mut R,
+    byte: u8,
+    left: u8,
+}
+
+impl<'a, R: Read> BitReader<'a, R> {
+    fn new(reader: &'a mut R) -> Self {
+        BitReader { reader, byte: 0, left: 0 }
+    }
+
+    fn read_bit(&mut self) -> io::Result<bool> {
+        if self.left == 0 {
+            self.byte = read_u8(self.reader)?;
+            self.left = 8;
+        }
+        self.left -= 1;
+        Ok(self.byte >> self.left & 1 == 1)
+    }
+
+    fn read_byte(&mut self) -> io::Result<u8> {
+        let mut byte = 0u8;
+        for _ in 0..8 {
+            byte = byte << 1 | self.read_bit()? as u8;
+        }
+        Ok(byte)
+    }
+}
+
+// reconstrói a árvore gravada por write_tree (as frequências não são necessárias pra decodificar)
+fn read_tree<S: TreeSymbol>(bits: &mut BitReader<impl Read>, depth: usize) -> io::Result<Huffman<S>> {
+    if depth > MAX_TREE_DEPTH {
+        return Err(invalid_data("árvore profunda demais"));
+    }
+
+    if bits.read_bit()? {
+        Ok(Huffman::Leaf { symbol: S::read_leaf(bits)?, freq: 0 })
+    } else {
+        let left = read_tree(bits, depth + 1)?;
+        let right = read_tree(bits, depth + 1)?;
+        Ok(Huffman::Node { freq: 0, left: Box::new(left), right: Box::new(right) })
+    }
+}
+
+// anda na árvore bit a bit até achar o símbolo de fim; o resto do último byte é enchimento
+fn decode_until_eof<S: Symbol>(bits: &mut BitReader<impl Read>, tree: &Huffman<Option<S>>) -> io::Result<Vec<S>> {
+    if let Huffman::Leaf { symbol: Some(_), .. } = tree {
+        return Err(invalid_data("bloco sem símbolo de fim"));
+    }
+
+    let mut result = Vec::new();
+    let mut node = tree;
+    loop {
+        if let Huffman::Node { left, right, .. } = node {
+            node = if bits.read_bit()? { right } else { left };
+        }
+        match node {
+            Huffman::Leaf { symbol: Some(symbol), .. } => {
+                result.push(*symbol);
+                node = tree;
+            }
+            Huffman::Leaf { symbol: None, .. } => return Ok(result),
+            Huffman::Node { .. } => {}
+        }
+    }
+}
+
+// corpos MODE_TREE e MODE_BYTES: t, árvore em pré-ordem (completada até o byte) e bits codificados;
+// com MODE_EOF, árvore e bits seguidos até o símbolo de fim
+fn decode_tree_body<S: TreeSymbol>(reader: &mut impl Read, eof_symbol: bool) -> io::Result<Vec<S>> {
+    if eof_symbol {
+        let mut bits = BitReader::new(reader);
+        let huffman_tree = read_tree(&mut bits, 0)?;
+        return decode_until_eof(&mut bits, &huffman_tree);
+    }
+
+    let t = read_varint(reader)?;
+    let huffman_tree = read_tree(&mut BitReader::new(reader), 0)?;
+    decode_payload(reader, t, &huffman_tree)
+}
+
+// conta quantos bytes já foram lidos, pra informar o progresso da descompressão
+struct CountingReader<'c, R> {
+    inner: R,
+    count: &'c Cell<u64>,
+}
+
+impl<R: Read> Read for CountingReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.set(self.count.get() + n as u64);
+        Ok(n)
+    }
+}
+
+// lê o magic de um membro; None se o arquivo acabou exatamente antes dele
+fn read_magic(reader: &mut impl Read) -> io::Result<Option<[u8; 4]>> {
+    let mut magic = Vec::with_capacity(4);
+    reader.take(4).read_to_end(&mut magic)?;
+    match magic.len() {
+        0 => Ok(None),
+        4 => Ok(Some([magic[0], magic[1], magic[2], magic[3]])),
+        _ => Err(io::ErrorKind::UnexpectedEof.into()),
+    }
+}
+
+pub fn decompress_stream(reader: impl Read, writer: impl Write) -> io::Result<()> {
+    decompress_stream_with(reader, writer, &DecompressOptions::default(), &mut Hooks::default())
+}
+
+// arquivos com vários membros (gravados com append) saem concatenados, como no gzip
+pub fn decompress_stream_with(
+    reader: impl Read,
+    mut writer: impl Write,
+    options: &DecompressOptions,
+    hooks: &mut Hooks,
+) -> io::Result<()> {
+    let count = Cell::new(0);
+    let mut reader = CountingReader { inner: reader, count: &count };
+    let mut report = || hooks.report(count.get());
+
+    let mut magic = read_magic(&mut reader)?.ok_or(io::ErrorKind::UnexpectedEof)?;
+    loop {
+        if magic[..3] == MAGIC[..] && magic[3] == ENCRYPTED {
+            let password = options.password.as_deref().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "arquivo cifrado: é preciso informar a senha")
+            })?;
+            let mut decrypted = DecryptReader::new(&mut reader, password, &magic)?;
+            let inner = read_magic(&mut decrypted)?.ok_or(io::ErrorKind::UnexpectedEof)?;
+            read_container(inner, &mut decrypted, &mut writer, &mut report)?;
+            decrypted.finish()?;
+        } else {
+            read_container(magic, &mut reader, &mut writer, &mut report)?;
+        }
+
+        match read_magic(&mut reader)? {
+            Some(next) => magic = next,
+            None => break,
+        }
+    }
+
+    writer.flush()
+}
+
+// lê um bloco [modo][corpo] das versões 2 e 3 e devolve seus dados; None no MODE_END
+fn read_block(reader: &mut impl Read, version: u8) -> io::Result<Option<Vec<u8>>> {
+    let (mode, eof_symbol) = match read_u8(reader)? {
+        MODE_END => return Ok(None),
+        mode if version >= 3 => (mode & !MODE_EOF, mode & MODE_EOF != 0),
+        mode => (mode, false),
+    };
+    let block = match mode {
+        MODE_STORED if !eof_symbol => {
+            let len = read_len(reader, version)?;
+            let mut block = Vec::new();
+            if reader.by_ref().take(len).read_to_end(&mut block)? as u64 != len {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            block
+        }
+        MODE_HUFFMAN if !eof_symbol => decode_huffman_body(reader, version)?.into_bytes(),
+        MODE_TREE if version >= 3 => decode_tree_body::<char>(reader, eof_symbol)?.into_iter().collect::<String>().into_bytes(),
+        MODE_BYTES if version >= 3 => decode_tree_body::<u8>(reader, eof_symbol)?,
+        _ => return Err(invalid_data("modo de bloco desconhecido")),
+    };
+    Ok(Some(block))
+}
+
+// versão 4: marcador, bloco no formato da versão 3 e crc32 dos dados do bloco
+pub(crate) fn read_synced_block(reader: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut sync = [0u8; SYNC.len()];
+    reader.read_exact(&mut sync)?;
+    if sync != *SYNC {
+        return Err(invalid_data("marcador de bloco não encontrado"));
+    }
+    let Some(block) = read_block(reader, 3)? else {
+        return Ok(None);
+    };
+    if read_u32(reader)? != crc32fast::hash(&block) {
+        return Err(invalid_data("checksum do bloco não confere"));
+    }
+    Ok(Some(block))
+}
+
+// lê um container (cujo magic já foi lido) até o fim dos seus blocos
+fn read_container(
+    magic: [u8; 4],
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    report: &mut dyn FnMut() -> io::Result<()>,
+) -> io::Result<()> {
+    if magic[..3] != MAGIC[..] {
+        // formato antigo: os bytes já lidos fazem parte do cabeçalho
+        let decoded = decode_huffman_body(&mut magic.chain(reader), 0)?;
+        writer.write_all(decoded.as_bytes())?;
+        return report();
+    }
+
+    match magic[3] {
+        1 => match read_u8(reader)? {
+            MODE_STORED => {
+                io::copy(reader, writer)?;
+            }
+            MODE_HUFFMAN => {
+                let decoded = decode_huffman_body(reader, 1)?;
+                writer.write_all(decoded.as_bytes())?;
+            }
+            _ => return Err(invalid_data("modo de bloco desconhecido")),
+        },
+        version @ (2 | 3) => {
+            while let Some(block) = read_block(reader, version)? {
+                writer.write_all(&block)?;
+                report()?;
+            }
+        }
+        4 => {
+            while let Some(block) = read_synced_block(reader)? {
+                writer.write_all(&block)?;
+                report()?;
+            }
+        }
+        _ => return Err(invalid_data("versão desconhecida")),
+    }
+
+    report()
+}
+
+pub fn decompress(input_path: &str, output_path: &str) -> io::Result<()> {
+    decompress_with(input_path, output_path, &DecompressOptions::default(), &mut Hooks::default())
+}
+
+pub fn decompress_with(
+    input_path: &str,
+    output_path: &str,
+    options: &DecompressOptions,
+    hooks: &mut Hooks,
+) -> io::Result<()> {
+    let input = File::open(input_path)?;
+    if hooks.total.is_none() {
+        hooks.total = Some(input.metadata()?.len());
+    }
+    let writer = BufWriter::new(File::create(output_path)?);
+
+    if options.mmap {
+        // segurança: o arquivo não pode ser alterado por outro processo enquanto está mapeado
+        let data = unsafe { Mmap::map(&input)? };
+        return decompress_stream_with(&data[..], writer, options, hooks);
+    }
+    decompress_stream_with(BufReader::new(input), writer, options, hooks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::ScratchDir;
+
+    // caminhos de entrada, comprimido e saída num diretório temporário, apagado quando o
+    // ScratchDir sai de escopo (mesmo se o teste falhar)
+    fn scratch_dir(name: &str) -> (ScratchDir, String, String, String) {
+        let dir = ScratchDir::new(name).unwrap();
+        let path = |file: &str| dir.join(file).to_str().unwrap().to_string();
+        let (input, packed, output) = (path("in.txt"), path("in.bin"), path("out.txt"));
+        (dir, input, packed, output)
+    }
+
+    fn roundtrip(input: &[u8]) -> Vec<u8> {
+        let mut compressed = Vec::new();
+        compress_stream(input, &mut compressed).unwrap();
+        let mut output = Vec::new();
+        decompress_stream(compressed.as_slice(), &mut output).unwrap();
+        assert_eq!(output, input);
+        compressed
+    }
+
+    #[test]
+    fn test_roundtrip_text() {
+        let text = "abracadabra ".repeat(100);
+        let compressed = roundtrip(text.as_bytes());
+        assert!(compressed.len() < text.len());
+        roundtrip("ç€😀 ascii".repeat(50).as_bytes());
+        roundtrip(b"");
+        roundtrip("zzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz".as_bytes());
+    }
+
+    #[test]
+    fn test_roundtrip_eof_symbol() {
+        let options = CompressOptions { eof_symbol: true, ..CompressOptions::default() };
+        let text = "ção".repeat(BLOCK_SIZE / 4) + &"abracadabra ".repeat(100);
+        let binary: Vec<u8> = (0..4000u32).map(|i| [0xFF, 0x00, 0xC3][(i % 3) as usize]).collect();
+        for input in [text.as_bytes(), &binary, b"x", b""] {
+            let mut compressed = Vec::new();
+            compress_stream_with(input, &mut compressed, &options, &mut Hooks::default()).unwrap();
+            let mut output = Vec::new();
+            decompress_stream(compressed.as_slice(), &mut output).unwrap();
+            assert_eq!(output, input);
+        }
+    }
+
+    #[test]
+    fn test_progress_and_cancel() {
+        use crate::progress::{CancelToken, is_cancelled};
+
+        let input = "abc".repeat(BLOCK_SIZE);
+        let mut reports = Vec::new();
+        let mut hooks = Hooks {
+            on_progress: Some(Box::new(|done, total| reports.push((done, total)))),
+            total: Some(input.len() as u64),
+            ..Hooks::default()
+        };
+        let mut compressed = Vec::new();
+        compress_stream_with(input.as_bytes(), &mut compressed, &CompressOptions::default(), &mut hooks).unwrap();
+        drop(hooks);
+        let total = input.len() as u64;
+        assert_eq!(reports, [(BLOCK_SIZE as u64, Some(total)), (2 * BLOCK_SIZE as u64, Some(total)), (total, Some(total))]);
+
+        // cancela depois do primeiro bloco
+        let cancel = CancelToken::new();
+        let token = cancel.clone();
+        let mut hooks = Hooks {
+            on_progress: Some(Box::new(move |_, _| token.cancel())),
+            cancel: Some(cancel),
+            ..Hooks::default()
+        };
+        let mut output = Vec::new();
+        let e = decompress_stream_with(compressed.as_slice(), &mut output, &DecompressOptions::default(), &mut hooks)
+            .unwrap_err();
+        assert!(is_cancelled(&e));
+        assert_eq!(output.len(), BLOCK_SIZE);
+    }
+
+    #[test]
+    fn test_encrypted_roundtrip_and_tampering() {
+        let password = Some(String::from("correct horse battery staple"));
+        let options = CompressOptions { password: password.clone(), ..CompressOptions::default() };
+        let input = "abracadabra ".repeat(20_000);
+        let mut sealed = Vec::new();
+        compress_stream_with(input.as_bytes(), &mut sealed, &options, &mut Hooks::default()).unwrap();
+        assert_eq!(sealed[..4], [b'H', b'U', b'F', ENCRYPTED]);
+
+        let open = |data: &[u8], password: Option<&str>| {
+            let options = DecompressOptions { password: password.map(String::from), ..DecompressOptions::default() };
+            let mut output = Vec::new();
+            decompress_stream_with(data, &mut output, &options, &mut Hooks::default()).map(|_| output)
+        };
+        assert_eq!(open(&sealed, password.as_deref()).unwrap(), input.as_bytes());
+
+        assert_eq!(open(&sealed, None).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(open(&sealed, Some("errada")).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // sal (cabeçalho), corpo e fim adulterados ou truncados
+        for i in [10, sealed.len() / 2, sealed.len() - 1] {
+            let mut tampered = sealed.clone();
+            tampered[i] ^= 1;
+            assert!(open(&tampered, password.as_deref()).is_err());
+        }
+        assert!(open(&sealed[..sealed.len() - 1], password.as_deref()).is_err());
+    }
+
+    #[test]
+    fn test_rejects_inflated_key_costs() {
+        // 1 GiB, 64 passadas e 16 threads: seria aceito pelos limites antigos
+        let options = DecompressOptions { password: Some(String::from("senha")), ..DecompressOptions::default() };
+        for costs in [[1 << 20, 2, 1], [19 * 1024, 64, 1], [19 * 1024, 2, 16]] {
+            let mut header = vec![b'H', b'U', b'F', ENCRYPTED, 1];
+            for cost in costs {
+                write_varint(&mut header, cost);
+            }
+            header.extend_from_slice(&[0u8; 16 + 7]);
+            let start = std::time::Instant::now();
+            let e = decompress_stream_with(header.as_slice(), &mut Vec::new(), &options, &mut Hooks::default())
+                .unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+            assert!(start.elapsed() < std::time::Duration::from_secs(1));
+        }
+    }
+
+    #[test]
+    fn test_mmap_roundtrip() {
+        let (_dir, input, packed, output) = scratch_dir("mmap");
+
+        let text = "ção".repeat(BLOCK_SIZE / 4) + "fim";
+        for data in [text.as_bytes(), b""] {
+            std::fs::write(&input, data).unwrap();
+            let options = CompressOptions { mmap: true, ..CompressOptions::default() };
+            compress_with(&input, &packed, &options, &mut Hooks::default()).unwrap();
+            let options = DecompressOptions { mmap: true, ..DecompressOptions::default() };
+            decompress_with(&packed, &output, &options, &mut Hooks::default()).unwrap();
+            assert_eq!(std::fs::read(&output).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_concatenated_members() {
+        let compress = |data: &[u8], options: &CompressOptions| {
+            let mut output = Vec::new();
+            compress_stream_with(data, &mut output, options, &mut Hooks::default()).unwrap();
+            output
+        };
+        let password = Some(String::from("senha"));
+        let plain = CompressOptions::default();
+        let eof = CompressOptions { eof_symbol: true, ..CompressOptions::default() };
+        let sealed = CompressOptions { password: password.clone(), ..CompressOptions::default() };
+
+        let mut archive = compress(b"primeira hora\n", &plain);
+        archive.extend(compress(b"", &plain));
+        archive.extend(compress(&[0xFF, 0x00, 0xFF], &eof));
+        archive.extend(compress(b"segunda hora\n", &sealed));
+        archive.extend(compress(b"terceira hora\n", &plain));
+
+        let options = DecompressOptions { password, ..DecompressOptions::default() };
+        let mut output = Vec::new();
+        decompress_stream_with(archive.as_slice(), &mut output, &options, &mut Hooks::default()).unwrap();
+        assert_eq!(output, b"primeira hora\n\xFF\x00\xFFsegunda hora\nterceira hora\n");
+
+        // membro cortado no meio do magic
+        archive.extend_from_slice(b"HU");
+        let mut output = Vec::new();
+        let e = decompress_stream_with(archive.as_slice(), &mut output, &options, &mut Hooks::default()).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+
+        // arquivo vazio não é um container válido
+        assert_eq!(decompress_stream(&b""[..], &mut Vec::new()).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_append_to_file() {
+        let (_dir, input, packed, output) = scratch_dir("append");
+
+        let options = CompressOptions { append: true, ..CompressOptions::default() };
+        for hour in 0..3 {
+            std::fs::write(&input, format!("log da hora {}\n", hour)).unwrap();
+            compress_with(&input, &packed, &options, &mut Hooks::default()).unwrap();
+        }
+        decompress(&packed, &output).unwrap();
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), "log da hora 0\nlog da hora 1\nlog da hora 2\n");
+    }
+
+    #[test]
+    fn test_cancelled_append_keeps_archive() {
+        use crate::progress::CancelToken;
+
+        let (dir, input, packed, output) = scratch_dir("append-cancel");
+        let options = CompressOptions { append: true, ..CompressOptions::default() };
+        let append = |data: &str, hooks: &mut Hooks| {
+            std::fs::write(&input, data).unwrap();
+            compress_with(&input, &packed, &options, hooks)
+        };
+
+        append("primeira hora\n", &mut Hooks::default()).unwrap();
+        let len = std::fs::metadata(&packed).unwrap().len();
+
+        // cancela depois do primeiro dos três blocos
+        let cancel = CancelToken::new();
+        let token = cancel.clone();
+        let mut hooks = Hooks {
+            on_progress: Some(Box::new(move |_, _| token.cancel())),
+            cancel: Some(cancel),
+            ..Hooks::default()
+        };
+        assert!(append(&"abc".repeat(BLOCK_SIZE), &mut hooks).is_err());
+        drop(hooks);
+        assert_eq!(std::fs::metadata(&packed).unwrap().len(), len);
+
+        append("terceira hora\n", &mut Hooks::default()).unwrap();
+        decompress(&packed, &output).unwrap();
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), "primeira hora\nterceira hora\n");
+        assert_eq!(std::fs::read_dir(&dir.0).unwrap().count(), 3);
+    }
+
+    #[test]
+    fn test_incompressible_is_stored() {
+        let data: Vec<u8> = (0..=255).cycle().take(3000).collect();
+        let compressed = roundtrip(&data);
+        assert!(compressed.len() <= data.len() + 30);
+    }
+
+    #[test]
+    fn test_falls_back_to_stored_mode() {
+        // modo do primeiro bloco: logo depois do magic e do marcador
+        let mode = |compressed: &[u8]| compressed[4 + SYNC.len()];
+        let random: Vec<u8> = (0..4000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8).collect();
+        for input in [&b"abc"[..], "ação".as_bytes(), &random] {
+            let compressed = roundtrip(input);
+            assert_eq!(mode(&compressed), MODE_STORED);
+        }
+        assert_eq!(mode(&roundtrip("abracadabra ".repeat(100).as_bytes())), MODE_TREE);
+    }
+
+    #[test]
+    fn test_binary_is_coded_by_byte() {
+        let data: Vec<u8> = (0..4000u32).map(|i| [0xFF, 0x00, 0xC3, 0x28][(i * i % 7 % 4) as usize]).collect();
+        assert!(std::str::from_utf8(&data).is_err());
+        let compressed = roundtrip(&data);
+        assert!(compressed.len() < data.len() / 2);
+    }
+
+    #[test]
+    fn test_tree_header_is_smaller_than_frequency_table() {
+        // 80 caracteres ascii distintos, como num bloco de texto comum
+        let text: String = (0..10_000u32).map(|i| char::from(b' ' + (i * 7 % 80) as u8)).collect();
+        let freq_table = count_chars_parallel(&text, 1);
+        assert_eq!(freq_table.len(), 80);
+
+        // a tabela antiga gastava 8 bytes por símbolo (caractere u32 + frequência u32)
+        let (tree_bits, _) = code_symbols(&freq_table, text.chars());
+        let header = tree_bits.len().div_ceil(8);
+        assert!(header * 4 < freq_table.len() * 8, "cabeçalho de {} bytes", header);
+    }
+
+    #[test]
+    fn test_blocks_keep_utf8_characters() {
+        let text = "ção".repeat(BLOCK_SIZE / 3);
+        roundtrip(text.as_bytes());
+    }
+
+    #[test]
+    fn test_varint_roundtrip() {
+        for value in [0, 1, 127, 128, 300, u32::MAX as u64 + 1, u64::MAX] {
+            let mut out = Vec::new();
+            write_varint(&mut out, value);
+            assert_eq!(read_varint(&mut out.as_slice()).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn test_rejects_overflowing_frequencies() {
+        // bloco MODE_HUFFMAN com duas frequências u64::MAX: a soma não cabe em 64 bits
+        let mut archive = vec![b'H', b'U', b'F', VERSION];
+        archive.extend_from_slice(SYNC);
+        archive.push(MODE_HUFFMAN);
+        write_varint(&mut archive, 2);
+        write_varint(&mut archive, 2);
+        for c in ['a', 'b'] {
+            archive.extend_from_slice(&(c as u32).to_be_bytes());
+            write_varint(&mut archive, u64::MAX);
+        }
+        archive.push(0b0100_0000);
+        let e = decompress_stream(archive.as_slice(), &mut Vec::new()).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+
+        let recovery = crate::recover_bytes(&archive, &mut Vec::new()).unwrap();
+        assert_eq!(recovery.blocks, 0);
+    }
+
+    #[test]
+    fn test_reads_old_format() {
+        // "ab" no formato antigo: n = 2, t = 2, pares (char, freq), bits "01" (ou "10")
+        let mut old = Vec::new();
+        old.extend_from_slice(&2u16.to_be_bytes());
+        old.extend_from_slice(&2u32.to_be_bytes());
+        for c in ['a', 'b'] {
+            old.extend_from_slice(&(c as u32).to_be_bytes());
+            old.extend_from_slice(&1u32.to_be_bytes());
+        }
+        old.push(0b0100_0000);
+        let mut output = Vec::new();
+        decompress_stream(old.as_slice(), &mut output).unwrap();
+        assert_eq!(output, b"ab");
+
+        // um arquivo antigo pode receber membros novos no fim
+        compress_stream(&b"cd"[..], &mut old).unwrap();
+        let mut output = Vec::new();
+        decompress_stream(old.as_slice(), &mut output).unwrap();
+        assert_eq!(output, b"abcd");
+    }
+}
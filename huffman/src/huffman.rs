@@ -0,0 +1,287 @@
+use std::collections::{BinaryHeap, HashMap};
+use std::cmp::Ordering;
+use std::hash::Hash;
+use std::thread;
+
+// qualquer tipo que possa ser contado e comparado serve de símbolo:
+// char, u8, tokens u16, enums da aplicação...
+pub trait Symbol: Ord + Hash + Copy {}
+
+impl<S: Ord + Hash + Copy> Symbol for S {}
+
+#[derive(Debug)]
+pub enum Huffman<S> {
+    Leaf { symbol: S, freq: u64 },
+    Node { freq: u64, left: Box<Huffman<S>>, right: Box<Huffman<S>> },
+}
+
+impl<S: Symbol> Huffman<S> {
+    pub fn get_freq(&self) -> u64 {
+        match self {
+            Huffman::Leaf { freq, .. } => *freq,
+            Huffman::Node { freq, .. } => *freq,
+        }
+    }
+
+    pub fn get_symbol(&self) -> S {
+        match self {
+            Huffman::Leaf { symbol, .. } => *symbol,
+            Huffman::Node { left, .. } => left.get_symbol(), // escolhe o primeiro símbolo da subarvore
+        }
+    }
+}
+
+impl<S: Symbol> PartialEq for Huffman<S> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.get_freq(), self.get_symbol()) == (other.get_freq(), other.get_symbol())
+    }
+}
+
+impl<S: Symbol> Eq for Huffman<S> {}
+
+impl<S: Symbol> Ord for Huffman<S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.get_freq().cmp(&other.get_freq()) {
+            Ordering::Equal => self.get_symbol().cmp(&other.get_symbol()).reverse(),
+            other => other.reverse(), // menor frequência tem maior prioridade no heap
+        }
+    }
+}
+
+
+impl<S: Symbol> PartialOrd for Huffman<S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+pub fn build_frequency_table<S: Symbol>(symbols: impl IntoIterator<Item = S>) -> HashMap<S, u64> {
+    let mut freq = HashMap::new();
+    for s in symbols {
+        *freq.entry(s).or_insert(0) += 1;
+    }
+    freq
+}
+
+
+// conta os bytes em até `threads` pedaços paralelos, cada um num array de 256 posições;
+// no fim os arrays são somados
+pub fn count_bytes_parallel(bytes: &[u8], threads: usize) -> HashMap<u8, u64> {
+    fn count(chunk: &[u8]) -> [u64; 256] {
+        let mut counts = [0u64; 256];
+        for &b in chunk {
+            counts[b as usize] += 1;
+        }
+        counts
+    }
+
+    let chunk_size = bytes.len().div_ceil(threads.max(1)).max(1);
+    let partials: Vec<[u64; 256]> = if threads <= 1 {
+        vec![count(bytes)]
+    } else {
+        thread::scope(|scope| {
+            let handles: Vec<_> = bytes.chunks(chunk_size).map(|chunk| scope.spawn(move || count(chunk))).collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        })
+    };
+
+    let mut total = [0u64; 256];
+    for counts in &partials {
+        for (t, c) in total.iter_mut().zip(counts) {
+            *t += c;
+        }
+    }
+    (0..=255u8).zip(total).filter(|&(_, f)| f > 0).collect()
+}
+
+// mesma ideia pra texto: os pedaços terminam em fronteiras de caractere,
+// ascii é contado num array e o resto num HashMap
+pub fn count_chars_parallel(text: &str, threads: usize) -> HashMap<char, u64> {
+    fn count(chunk: &str) -> ([u64; 128], HashMap<char, u64>) {
+        let mut ascii = [0u64; 128];
+        let mut others = HashMap::new();
+        for c in chunk.chars() {
+            if c.is_ascii() {
+                ascii[c as usize] += 1;
+            } else {
+                *others.entry(c).or_insert(0) += 1;
+            }
+        }
+        (ascii, others)
+    }
+
+    let threads = threads.max(1);
+    let mut chunks = Vec::with_capacity(threads);
+    let mut start = 0;
+    for i in 1..=threads {
+        let mut end = text.len() * i / threads;
+        while !text.is_char_boundary(end) {
+            end += 1;
+        }
+        if end > start {
+            chunks.push(&text[start..end]);
+            start = end;
+        }
+    }
+
+    let partials: Vec<_> = if chunks.len() <= 1 {
+        chunks.into_iter().map(count).collect()
+    } else {
+        thread::scope(|scope| {
+            let handles: Vec<_> = chunks.into_iter().map(|chunk| scope.spawn(move || count(chunk))).collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        })
+    };
+
+    let mut freq = HashMap::new();
+    for (ascii, others) in partials {
+        for (c, f) in (0..128u8).zip(ascii).filter(|&(_, f)| f > 0) {
+            *freq.entry(c as char).or_insert(0) += f;
+        }
+        for (c, f) in others {
+            *freq.entry(c).or_insert(0) += f;
+        }
+    }
+    freq
+}
+
+
+// precisa de pelo menos um símbolo na tabela
+pub fn build_huffman_tree<S: Symbol>(freq: &HashMap<S, u64>) -> Huffman<S> {
+    build_huffman_tree_with(freq, |_, _| {})
+}
+
+// igual ao build_huffman_tree, mas chama on_merge com os dois nós tirados do heap antes de juntá-los
+pub fn build_huffman_tree_with<S: Symbol>(
+    freq: &HashMap<S, u64>,
+    mut on_merge: impl FnMut(&Huffman<S>, &Huffman<S>),
+) -> Huffman<S> {
+    let mut heap: BinaryHeap<Huffman<S>> = freq.iter()
+        .map(|(&symbol, &f)| Huffman::Leaf { symbol, freq: f })
+        .collect();
+    // enquanto tem mais de um nó, combina dois nós menores.
+    while heap.len() > 1 {
+        let min1 = heap.pop().unwrap();
+        let min2 = heap.pop().unwrap();
+        on_merge(&min1, &min2);
+        let new_node = Huffman::Node {
+            freq: min1.get_freq() + min2.get_freq(),
+            left: Box::new(min1),
+            right: Box::new(min2),
+        };
+        heap.push(new_node);
+    }
+
+    heap.pop().unwrap()
+}
+
+
+pub fn build_codes<S: Symbol>(tree: &Huffman<S>, prefix: String, codes: &mut HashMap<S, String>) {
+    match tree {
+        Huffman::Leaf { symbol, .. } => {
+            // árvore com um único símbolo: usa "0" pra que cada ocorrência gaste um bit
+            let code = if prefix.is_empty() { String::from("0") } else { prefix };
+            codes.insert(*symbol, code);
+        }
+        Huffman::Node { left, right, .. } => {
+            build_codes(left, format!("{}0", prefix), codes);
+            build_codes(right, format!("{}1", prefix), codes);
+        }
+    }
+}
+
+
+pub fn encode<S: Symbol>(symbols: impl IntoIterator<Item = S>, codes: &HashMap<S, String>) -> String {
+    symbols.into_iter()
+        .map(|s| codes.get(&s).unwrap().as_str())
+        .collect()
+}
+
+
+pub fn decode<S: Symbol>(bits: &str, tree: &Huffman<S>) -> Vec<S> {
+    let mut result = Vec::new();
+    let mut node = tree;
+
+    for b in bits.chars() {
+        if let Huffman::Node { left, right, .. } = node {
+            node = if b == '0' { left } else { right };
+        }
+        // chegou numa folha: emite o símbolo e volta pra raiz
+        if let Huffman::Leaf { symbol, .. } = node {
+            result.push(*symbol);
+            node = tree;
+        }
+    }
+
+    result
+}
+
+
+// função auxiliar: converte string de bits para vetor de bytes
+pub fn bits_to_bytes(bits: &str) -> Vec<u8> {
+    bits.as_bytes()
+        .chunks(8)
+        .map(|chunk| {
+            let mut byte = 0u8;
+            for (i, &b) in chunk.iter().enumerate() {
+                if b == b'1' {
+                    byte |= 1 << (7 - i);
+                }
+            }
+            byte
+        })
+        .collect()
+}
+
+// converte bytes pra uma string de bits
+pub fn bytes_to_bits(bytes: &[u8]) -> String {
+    bytes.iter()
+        .map(|byte| format!("{:08b}", byte))
+        .collect::<Vec<_>>()
+        .concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    enum Token {
+        Literal(u8),
+        Match { length: u16, distance: u16 },
+    }
+
+    #[test]
+    fn test_generic_symbols() {
+        let tokens = [
+            Token::Literal(b'a'),
+            Token::Match { length: 3, distance: 1 },
+            Token::Literal(b'b'),
+            Token::Literal(b'a'),
+            Token::Match { length: 3, distance: 1 },
+            Token::Literal(b'a'),
+        ];
+        let tree = build_huffman_tree(&build_frequency_table(tokens));
+        let mut codes = HashMap::new();
+        build_codes(&tree, String::new(), &mut codes);
+        assert_eq!(decode(&encode(tokens, &codes), &tree), tokens);
+
+        let words: Vec<u16> = vec![500, 500, 500, 7, 7, 65535];
+        let tree = build_huffman_tree(&build_frequency_table(words.iter().copied()));
+        let mut codes = HashMap::new();
+        build_codes(&tree, String::new(), &mut codes);
+        assert_eq!(codes[&500].len(), 1);
+        assert_eq!(decode(&encode(words.iter().copied(), &codes), &tree), words);
+    }
+
+    #[test]
+    fn test_parallel_counts_match_sequential() {
+        let text = "ação, 😀 e ascii ".repeat(1000);
+        for threads in [1, 2, 3, 8, 64] {
+            assert_eq!(count_chars_parallel(&text, threads), build_frequency_table(text.chars()));
+            assert_eq!(count_bytes_parallel(text.as_bytes(), threads), build_frequency_table(text.bytes()));
+        }
+        assert!(count_chars_parallel("", 4).is_empty());
+        assert!(count_bytes_parallel(b"", 4).is_empty());
+    }
+}
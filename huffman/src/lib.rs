@@ -0,0 +1,16 @@
+pub mod bench;
+mod crypto;
+mod explain;
+mod format;
+pub mod ffi;
+pub mod huffman;
+pub mod progress;
+mod recover;
+
+pub use format::{
+    CompressOptions, DecompressOptions, compress, compress_stream, compress_stream_with, compress_with, decompress,
+    decompress_stream, decompress_stream_with, decompress_with,
+};
+pub use explain::explain;
+pub use progress::{CancelToken, Hooks};
+pub use recover::{Recovery, recover, recover_bytes};
@@ -1,231 +1,128 @@
-use std::collections::{BinaryHeap, HashMap};
-use std::cmp::Ordering;
+use std::env;
 use std::fs::File;
-use std::io::{Read, Write, BufReader, BufWriter};
+use std::path::Path;
+use std::io::{self, BufRead, IsTerminal};
 
-#[derive(Debug, Eq)]
-enum Huffman {
-    Leaf { c: char, freq: u32 },
-    Node { freq: u32, left: Box<Huffman>, right: Box<Huffman> },
-}
-
-impl Huffman {
-    fn get_freq(&self) -> u32 {
-        match self {
-            Huffman::Leaf { freq, .. } => *freq,
-            Huffman::Node { freq, .. } => *freq,
-        }
-    }
-
-    fn get_char(&self) -> char {
-        match self {
-            Huffman::Leaf { c, .. } => *c,
-            Huffman::Node { left, .. } => left.get_char(), // escolhe o primeiro caractere da subarvore
-        }
-    }
-}
+use huffman::bench::{run_benchmark, write_csv, write_markdown};
+use huffman::{CompressOptions, DecompressOptions, Hooks, compress_with, decompress_with, explain, recover};
 
-impl PartialEq for Huffman {
-    fn eq(&self, other: &Self) -> bool {
-        (self.get_freq(), self.get_char()) == (other.get_freq(), other.get_char())
-    }
+fn mib(bytes: u64) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
 }
 
-impl Ord for Huffman {
-    fn cmp(&self, other: &Self) -> Ordering {
-        match self.get_freq().cmp(&other.get_freq()) {
-            Ordering::Equal => self.get_char().cmp(&other.get_char()).reverse(),
-            other => other.reverse(), // menor frequência tem maior prioridade no heap
+fn draw_progress(done: u64, total: Option<u64>) {
+    const WIDTH: u64 = 40;
+    match total {
+        Some(total) if total > 0 => {
+            let filled = (done.min(total) * WIDTH / total) as usize;
+            eprint!(
+                "\r[{}{}] {:3}% {:.1}/{:.1} MiB",
+                "#".repeat(filled),
+                ".".repeat(WIDTH as usize - filled),
+                done.min(total) * 100 / total,
+                mib(done),
+                mib(total)
+            );
         }
+        _ => eprint!("\r{:.1} MiB", mib(done)),
     }
 }
 
-
-impl PartialOrd for Huffman {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+// barra de progresso no stderr, só quando ele é um terminal
+fn progress_hooks<'a>() -> Hooks<'a> {
+    if !io::stderr().is_terminal() {
+        return Hooks::default();
     }
+    Hooks { on_progress: Some(Box::new(draw_progress)), ..Hooks::default() }
 }
 
-fn build_frequency_table(text: &str) -> HashMap<char, u32> {
-    let mut freq = HashMap::new();
-    for c in text.chars() {
-        *freq.entry(c).or_insert(0) += 1;
+// no modo explain, espera um Enter entre os passos quando a entrada é um terminal
+fn pause() {
+    if io::stdin().is_terminal() {
+        eprint!("(Enter pra continuar)");
+        let _ = io::stdin().lock().read_line(&mut String::new());
     }
-    freq
 }
 
-
-fn build_huffman_tree(freq: &HashMap<char, u32>) -> Huffman {
-    let mut heap: BinaryHeap<Huffman> = freq.iter()
-        .map(|(&c, &f)| Huffman::Leaf { c, freq: f })
-        .collect();
-    // enquanto tem mais de um nó, combina dois nós menores.
-    while heap.len() > 1 {
-        let min1 = heap.pop().unwrap();
-        let min2 = heap.pop().unwrap();
-        let new_node = Huffman::Node {
-            freq: min1.get_freq() + min2.get_freq(),
-            left: Box::new(min1),
-            right: Box::new(min2),
-        };
-        heap.push(new_node);
+fn run(f: impl FnOnce(&mut Hooks) -> io::Result<()>) -> io::Result<()> {
+    let mut hooks = progress_hooks();
+    let show = hooks.on_progress.is_some();
+    let result = f(&mut hooks);
+    if show {
+        eprintln!();
     }
-
-    heap.pop().unwrap()
+    result
 }
 
+// a senha vem do ambiente pra não aparecer na linha de comando (e no `ps`)
+const PASSWORD_VAR: &str = "HUFFMAN_PASSWORD";
 
-fn build_codes(tree: &Huffman, prefix: String, codes: &mut HashMap<char, String>) {
-    match tree {
-        Huffman::Leaf { c, .. } => {
-            codes.insert(*c, prefix);
-        }
-        Huffman::Node { left, right, .. } => {
-            build_codes(left, format!("{}0", prefix), codes);
-            build_codes(right, format!("{}1", prefix), codes);
-        }
-    }
+// grava <relatorio>.csv e <relatorio>.md e mostra a tabela no stdout
+fn bench(dir: &str, report: &str) -> io::Result<()> {
+    let results = run_benchmark(Path::new(dir))?;
+    write_csv(&results, &mut File::create(format!("{}.csv", report))?)?;
+    write_markdown(&results, &mut File::create(format!("{}.md", report))?)?;
+    write_markdown(&results, &mut io::stdout())
 }
 
-
-fn encode(text: &str, codes: &HashMap<char, String>) -> String {
-    text.chars()
-        .map(|c| codes.get(&c).unwrap().clone())
-        .collect()
+// salva os blocos intactos e lista os trechos danificados
+fn salvage(input: &str, output: &str) -> io::Result<()> {
+    let recovery = recover(input, output)?;
+    println!("{} blocos recuperados ({:.1} MiB)", recovery.blocks, mib(recovery.bytes));
+    for range in &recovery.damaged {
+        println!("bytes {}..{} danificados", range.start, range.end);
+    }
+    Ok(())
 }
 
-
-fn decode(bits: &str, tree: &Huffman) -> String {
-    let mut result = String::new();
-    let mut node = tree;
-
-    for b in bits.chars() {
-        node = match node {
-            Huffman::Node { left, right, .. } => {
-                if b == '0' { left } else { right }
+fn main() {
+    let mut options = CompressOptions::default();
+    let mut decompress_options = DecompressOptions { password: env::var(PASSWORD_VAR).ok(), mmap: false };
+    let mut args = Vec::new();
+    let mut iter = env::args();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--eof" => options.eof_symbol = true,
+            "--append" => options.append = true,
+            "--mmap" => {
+                options.mmap = true;
+                decompress_options.mmap = true;
             }
-            Huffman::Leaf { c, .. } => {
-                result.push(*c);
-                node = tree;
-                // reprocessa o bit atual (precisa voltar e processar ele no nó raiz)
-                node = match node {
-                    Huffman::Node { left, right, .. } => {
-                        if b == '0' { left } else { right }
-                    }
-                    _ => unreachable!()
-                };
-                node
+            "--encrypt" => {
+                options.password = Some(env::var(PASSWORD_VAR).unwrap_or_else(|_| {
+                    eprintln!("--encrypt precisa da senha em {}", PASSWORD_VAR);
+                    std::process::exit(2);
+                }));
             }
-        }
-    }
-
-    // caso o último nó seja folha, precisa adicionar o caractere.
-    if let Huffman::Leaf { c, .. } = node {
-        result.push(*c);
-    }
-
-    result
-}
-
-
-// função auxiliar: converte string de bits para vetor de bytes
-fn bits_to_bytes(bits: &str) -> Vec<u8> {
-    bits.as_bytes()
-        .chunks(8)
-        .map(|chunk| {
-            let mut byte = 0u8;
-            for (i, &b) in chunk.iter().enumerate() {
-                if b == b'1' {
-                    byte |= 1 << (7 - i);
-                }
+            "--threads" => {
+                options.threads = iter.next().and_then(|n| n.parse().ok()).unwrap_or_else(|| {
+                    eprintln!("--threads precisa de um número");
+                    std::process::exit(2);
+                });
             }
-            byte
-        })
-        .collect()
-}
-
-// converte bytes pra uma string de bits
-fn bytes_to_bits(bytes: &[u8]) -> String {
-    bytes.iter()
-        .map(|byte| format!("{:08b}", byte))
-        .collect::<Vec<_>>()
-        .concat()
-}
-
-fn compress(input_path: &str, output_path: &str) {
-    let mut input = String::new();
-    BufReader::new(File::open(input_path).unwrap()).read_to_string(&mut input).unwrap();
-
-    let freq_table = build_frequency_table(&input);
-    let huffman_tree = build_huffman_tree(&freq_table);
-
-    let mut codes = HashMap::new();
-    build_codes(&huffman_tree, String::new(), &mut codes);
-
-    let encoded = encode(&input, &codes);
-    
-    let padding = (8 - (encoded.len() % 8)) % 8;                         // calcular quantos bits faltam pra completar o byte.
-    let encoded_padded = format!("{}{}", encoded, "0".repeat(padding)); // adiciona zeros que faltam no final (big endian)
-
-    let encoded_bytes = bits_to_bytes(&encoded_padded);
-
-    let mut writer = BufWriter::new(File::create(output_path).unwrap());
-    let n = freq_table.len() as u16;
-    let t = encoded.len() as u32; // quantidade de bits codificados
-
-
-    writer.write_all(&n.to_be_bytes()).unwrap();
-    writer.write_all(&t.to_be_bytes()).unwrap();
-
-    for (c, f) in &freq_table {
-        let c_u32 = *c as u32;
-        writer.write_all(&c_u32.to_be_bytes()).unwrap(); // grava como u32 pra suportar unicode
-
-        writer.write_all(&f.to_be_bytes()).unwrap(); // escreve a frequência
+            _ => args.push(arg),
+        }
     }
-    
-    writer.write_all(&encoded_bytes).unwrap();
-}
 
-fn decompress(input_path: &str, output_path: &str) {
-    let mut reader = BufReader::new(File::open(input_path).unwrap());
-    let mut buffer = [0u8; 2];
-    reader.read_exact(&mut buffer).unwrap();
-    let n = u16::from_be_bytes(buffer); // número de caracteres distintos
-
-    let mut buffer = [0u8; 4];
-    reader.read_exact(&mut buffer).unwrap();
-    let t = u32::from_be_bytes(buffer); // total de caracteres
-
-    let mut freq_table = HashMap::new();
-    for _ in 0..n {
-        let mut c_buf = [0u8; 4];
-        reader.read_exact(&mut c_buf).unwrap();
-        let c = char::from_u32(u32::from_be_bytes(c_buf)).unwrap();
+    let result = match args.get(1).map(String::as_str) {
+        Some("compress") if args.len() == 4 => run(|hooks| compress_with(&args[2], &args[3], &options, hooks)),
+        Some("recover") if args.len() == 4 => salvage(&args[2], &args[3]),
+        Some("bench") if args.len() == 4 => bench(&args[2], &args[3]),
+        Some("explain") if args.len() == 3 => explain(&args[2], &mut io::stdout(), &mut pause),
+        Some("decompress") if args.len() == 4 => run(|hooks| decompress_with(&args[2], &args[3], &decompress_options, hooks)),
+        None => run(|hooks| compress_with("input.txt", "file.bin", &options, hooks))
+            .and_then(|_| run(|hooks| decompress_with("file.bin", "out.txt", &decompress_options, hooks))),
+        _ => {
+            eprintln!("uso: {} [compress [--eof] [--threads N] [--encrypt] [--append]|decompress] [--mmap] <entrada> <saida>", args[0]);
+            eprintln!("     {} recover <entrada> <saida>", args[0]);
+            eprintln!("     {} explain <texto>", args[0]);
+            eprintln!("     {} bench <diretório> <relatório>", args[0]);
+            std::process::exit(2);
+        }
+    };
 
-    
-        let mut f = [0u8; 4];
-        reader.read_exact(&mut f).unwrap();
-        let f = u32::from_be_bytes(f);
-    
-        freq_table.insert(c, f);
+    if let Err(e) = result {
+        eprintln!("erro: {}", e);
+        std::process::exit(1);
     }
-    
-
-    let mut encoded_bytes = Vec::new();
-    reader.read_to_end(&mut encoded_bytes).unwrap();
-    let bits = bytes_to_bits(&encoded_bytes);
-    let useful_bits = &bits[..t as usize];
-
-    let huffman_tree = build_huffman_tree(&freq_table);
-    let decoded = decode(useful_bits, &huffman_tree);
-
-    let mut writer = BufWriter::new(File::create(output_path).unwrap());
-    writer.write_all(decoded.as_bytes()).unwrap();
-}
-
-fn main() {
-    compress("input.txt", "file.bin");
-    decompress("file.bin", "out.txt");
 }
@@ -0,0 +1,66 @@
+// acompanhamento e cancelamento de compressões/descompressões longas.
+// os dois são verificados entre os blocos, nunca no meio de um.
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// pode ser clonado e passado pra outra thread, que chama cancel()
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+// erro devolvido (dentro de um io::Error) quando a operação é cancelada
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "operação cancelada")
+    }
+}
+
+impl Error for Cancelled {}
+
+pub fn is_cancelled(e: &io::Error) -> bool {
+    e.get_ref().is_some_and(|inner| inner.is::<Cancelled>())
+}
+
+// recebe (bytes de entrada processados, total de bytes da entrada)
+pub type ProgressFn<'a> = Box<dyn FnMut(u64, Option<u64>) + 'a>;
+
+#[derive(Default)]
+pub struct Hooks<'a> {
+    // chamado depois de cada bloco
+    pub on_progress: Option<ProgressFn<'a>>,
+    pub cancel: Option<CancelToken>,
+    // tamanho da entrada, se conhecido; as funções que recebem caminhos usam o tamanho do arquivo
+    pub total: Option<u64>,
+}
+
+impl Hooks<'_> {
+    // avisa o progresso e para com Cancelled se o token foi acionado
+    pub(crate) fn report(&mut self, done: u64) -> io::Result<()> {
+        if let Some(on_progress) = &mut self.on_progress {
+            on_progress(done, self.total);
+        }
+        match &self.cancel {
+            Some(cancel) if cancel.is_cancelled() => Err(io::Error::other(Cancelled)),
+            _ => Ok(()),
+        }
+    }
+}
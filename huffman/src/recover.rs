@@ -0,0 +1,111 @@
+// modo de recuperação: varre o arquivo atrás dos marcadores de bloco (versão 4) e salva
+// todos os blocos cujo crc confere. o que sobra entre eles é informado como danificado.
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+
+use memmap2::Mmap;
+
+use crate::format::{ENCRYPTED, MAGIC, SYNC, VERSION, read_synced_block};
+
+#[derive(Debug, Default)]
+pub struct Recovery {
+    // blocos intactos e quantos bytes eles tinham
+    pub blocks: usize,
+    pub bytes: u64,
+    // trechos do arquivo comprimido que não puderam ser lidos
+    pub damaged: Vec<Range<u64>>,
+}
+
+pub fn recover_bytes(data: &[u8], writer: &mut impl Write) -> io::Result<Recovery> {
+    if data.len() >= 4 && data[..3] == MAGIC[..] && data[3] == ENCRYPTED {
+        // o envelope cifrado não tem marcadores, e um pedaço alterado não passa na autenticação
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "arquivo cifrado não pode ser recuperado"));
+    }
+
+    let mut recovery = Recovery::default();
+    let mut damaged_from = None;
+    let mut pos = 0;
+    while pos < data.len() {
+        let rest = &data[pos..];
+        let len = if rest.starts_with(MAGIC) && rest.get(3) == Some(&VERSION) {
+            // cabeçalho de um membro
+            Some(4)
+        } else if rest.starts_with(SYNC) {
+            let mut reader = rest;
+            match read_synced_block(&mut reader) {
+                Ok(Some(block)) => {
+                    writer.write_all(&block)?;
+                    recovery.blocks += 1;
+                    recovery.bytes += block.len() as u64;
+                    Some(rest.len() - reader.len())
+                }
+                Ok(None) => Some(rest.len() - reader.len()),
+                // marcador danificado, ou que só apareceu por acaso dentro dos dados
+                Err(_) => None,
+            }
+        } else {
+            None
+        };
+
+        match len {
+            Some(len) => {
+                if let Some(start) = damaged_from.take() {
+                    recovery.damaged.push(start..pos as u64);
+                }
+                pos += len;
+            }
+            None => {
+                damaged_from.get_or_insert(pos as u64);
+                pos += 1;
+            }
+        }
+    }
+    if let Some(start) = damaged_from {
+        recovery.damaged.push(start..data.len() as u64);
+    }
+
+    writer.flush()?;
+    Ok(recovery)
+}
+
+// o arquivo é mapeado em vez de lido: arquivos de vários GB não precisam caber na memória
+pub fn recover(input_path: &str, output_path: &str) -> io::Result<Recovery> {
+    let input = File::open(input_path)?;
+    // segurança: o arquivo não pode ser alterado por outro processo enquanto está mapeado
+    let data = unsafe { Mmap::map(&input)? };
+    recover_bytes(&data, &mut BufWriter::new(File::create(output_path)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compress_stream, decompress_stream};
+
+    #[test]
+    fn test_recovers_intact_blocks() {
+        const BLOCK: usize = 1 << 20;
+        let text: String = (0..).map(|i| format!("linha {}\n", i)).take(300_000).collect();
+        let data = &text.as_bytes()[..BLOCK * 5 / 2];
+        let mut archive = Vec::new();
+        compress_stream(data, &mut archive).unwrap();
+        compress_stream(&b"outro membro"[..], &mut archive).unwrap();
+
+        let mut output = Vec::new();
+        let recovery = recover_bytes(&archive, &mut output).unwrap();
+        assert_eq!((recovery.blocks, recovery.damaged.len()), (4, 0));
+        assert_eq!(output, [data, b"outro membro"].concat());
+
+        // estraga um byte no meio do segundo bloco
+        let middle = archive.len() / 2;
+        archive[middle] ^= 0x55;
+        assert!(decompress_stream(archive.as_slice(), &mut Vec::new()).is_err());
+
+        let mut output = Vec::new();
+        let recovery = recover_bytes(&archive, &mut output).unwrap();
+        assert_eq!(recovery.blocks, 3);
+        assert_eq!(recovery.damaged.len(), 1);
+        assert!(recovery.damaged[0].contains(&(middle as u64)));
+        assert_eq!(output, [&data[..BLOCK], &data[BLOCK * 2..], b"outro membro"].concat());
+    }
+}
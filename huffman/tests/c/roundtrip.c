@@ -0,0 +1,65 @@
+/* comprime e descomprime um arquivo pela api em C e confere se voltou igual.
+ * uso: roundtrip <arquivo> */
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "huffman.h"
+
+static uint8_t *read_file(const char *path, size_t *len) {
+    FILE *f = fopen(path, "rb");
+    if (!f) return NULL;
+    fseek(f, 0, SEEK_END);
+    long size = ftell(f);
+    fseek(f, 0, SEEK_SET);
+    uint8_t *data = malloc(size > 0 ? size : 1);
+    *len = fread(data, 1, size, f);
+    fclose(f);
+    return data;
+}
+
+int main(int argc, char **argv) {
+    if (argc != 2) {
+        fprintf(stderr, "uso: %s <arquivo>\n", argv[0]);
+        return 2;
+    }
+
+    size_t input_len;
+    uint8_t *input = read_file(argv[1], &input_len);
+    if (!input) {
+        perror(argv[1]);
+        return 2;
+    }
+
+    uint8_t *compressed, *output;
+    size_t compressed_len, output_len;
+    int32_t err = huffman_compress(input, input_len, &compressed, &compressed_len);
+    if (err != HUFFMAN_OK) {
+        fprintf(stderr, "huffman_compress: erro %d\n", err);
+        return 1;
+    }
+    err = huffman_decompress(compressed, compressed_len, &output, &output_len);
+    if (err != HUFFMAN_OK) {
+        fprintf(stderr, "huffman_decompress: erro %d\n", err);
+        return 1;
+    }
+
+    int ok = output_len == input_len && memcmp(output, input, input_len) == 0;
+    printf("%zu -> %zu -> %zu bytes: %s\n", input_len, compressed_len, output_len, ok ? "ok" : "DIFERENTE");
+
+    huffman_free(output, output_len);
+
+    /* um arquivo truncado tem que dar erro, não travar nem ler fora do buffer.
+     * em caso de erro a biblioteca não aloca nada e deixa truncated em NULL */
+    uint8_t *truncated;
+    size_t truncated_len;
+    if (huffman_decompress(compressed, compressed_len / 2, &truncated, &truncated_len) == HUFFMAN_OK) {
+        fprintf(stderr, "arquivo truncado foi aceito\n");
+        huffman_free(truncated, truncated_len);
+        ok = 0;
+    }
+
+    huffman_free(compressed, compressed_len);
+    free(input);
+    return ok ? 0 : 1;
+}
@@ -0,0 +1,35 @@
+// compila tests/c/roundtrip.c com o compilador C do sistema contra a libhuffman.a
+// e roda o round-trip em input.txt.
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn test_c_program_roundtrip() {
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // o executável do teste fica em target/<perfil>/deps; a biblioteca, em target/<perfil>
+    let exe = std::env::current_exe().unwrap();
+    let profile_dir = exe.parent().unwrap().parent().unwrap();
+
+    // cargo test não gera a staticlib, então ela é construída aqui
+    let mut build = Command::new(env!("CARGO"));
+    build.args(["build", "--lib", "--manifest-path"]).arg(manifest.join("Cargo.toml"));
+    if profile_dir.ends_with("release") {
+        build.arg("--release");
+    }
+    assert!(build.status().unwrap().success());
+
+    let program = profile_dir.join("c_roundtrip");
+    let status = Command::new(std::env::var("CC").unwrap_or_else(|_| String::from("cc")))
+        .arg(manifest.join("tests/c/roundtrip.c"))
+        .arg("-I")
+        .arg(manifest.join("include"))
+        .arg(profile_dir.join("libhuffman.a"))
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&program)
+        .status()
+        .expect("compilador C não encontrado");
+    assert!(status.success());
+
+    let status = Command::new(&program).arg(manifest.join("input.txt")).status().unwrap();
+    assert!(status.success());
+}
@@ -0,0 +1,27 @@
+// gera o cabeçalho C a partir de src/ffi.rs e confere se é igual ao include/huffman.h
+// guardado no repositório. com HUFFMAN_UPDATE_HEADER=1, grava o cabeçalho novo no lugar.
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn test_header_matches_ffi() {
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let config = cbindgen::Config::from_file(manifest.join("cbindgen.toml")).unwrap();
+    let mut generated = Vec::new();
+    cbindgen::Builder::new()
+        .with_crate(manifest)
+        .with_config(config)
+        .generate()
+        .expect("não foi possível gerar o cabeçalho")
+        .write(&mut generated);
+
+    let path = manifest.join("include/huffman.h");
+    if std::env::var_os("HUFFMAN_UPDATE_HEADER").is_some() {
+        fs::write(&path, &generated).unwrap();
+    }
+    let current = fs::read(&path).unwrap();
+    assert!(
+        current == generated,
+        "include/huffman.h está desatualizado: rode HUFFMAN_UPDATE_HEADER=1 cargo test --test header"
+    );
+}